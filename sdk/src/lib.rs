@@ -0,0 +1,328 @@
+//! Everything needed to write a native lanch plugin.
+//!
+//! A plugin is a `cdylib` exporting a [`PluginVTable`] under the symbol `lanch_plugin`. Only
+//! plain C types cross the library boundary, so lanch and the plugin don't have to be built
+//! with the same compiler version. Plugins don't render anything themselves, they describe
+//! their suggestions with [`Item`]s which lanch displays like any other suggestion.
+//!
+//! ```
+//! use lanch_plugin_sdk::{export_plugin, Item, Plugin};
+//!
+//! #[derive(Default)]
+//! struct Hello;
+//!
+//! impl Plugin for Hello {
+//!     fn name(&self) -> &str {
+//!         "hello"
+//!     }
+//!
+//!     fn get_matches(&self, query: &str, out: &mut Vec<Item>) {
+//!         out.push(Item::new(format!("Hello {query}")).exec("notify-send hello"));
+//!     }
+//! }
+//!
+//! export_plugin!(Hello);
+//! # fn main() {}
+//! ```
+
+use std::ffi::{c_char, c_void, CStr, CString};
+
+/// Bumped on every incompatible change of the types in this crate
+pub const ABI_VERSION: u32 = 1;
+
+/// Name of the symbol lanch looks up in the plugin library
+pub const ENTRY_SYMBOL: &[u8] = b"lanch_plugin\0";
+
+/// A suggestion as handed over to lanch. Strings are nul terminated UTF-8 and only need to
+/// stay valid for the duration of the callback they are passed to, nullable ones may be null.
+#[repr(C)]
+pub struct RawItem {
+    pub title: *const c_char,
+    pub subtitle: *const c_char,
+    // icon name or absolute path
+    pub icon: *const c_char,
+    // command run when the item is selected
+    pub exec: *const c_char,
+    // 0 to 100, 100 being an exact match
+    pub score: u8,
+}
+
+/// Called by the plugin once for every match
+pub type PushFn = extern "C" fn(ctx: *mut c_void, item: *const RawItem);
+
+#[repr(C)]
+pub struct PluginVTable {
+    pub abi_version: u32,
+
+    // creates the plugin state from the module's config section (a JSON object)
+    pub init: extern "C" fn(settings: *const c_char) -> *mut c_void,
+
+    // static, nul terminated strings. The name is needed before `init` to find the module's
+    // config section, so it comes from the default plugin rather than the state.
+    pub name: extern "C" fn() -> *const c_char,
+    pub description: extern "C" fn(state: *const c_void) -> *const c_char,
+
+    // may be called from several threads at once
+    pub get_matches:
+        extern "C" fn(state: *const c_void, query: *const c_char, push: PushFn, ctx: *mut c_void),
+
+    // frees the state, nothing else is called afterwards
+    pub shutdown: extern "C" fn(state: *mut c_void),
+}
+
+/// Safe counterpart of [`RawItem`]
+#[derive(Debug, Clone, Default)]
+pub struct Item {
+    pub title: String,
+    pub subtitle: Option<String>,
+    pub icon: Option<String>,
+    pub exec: Option<String>,
+    pub score: u8,
+}
+
+impl Item {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            score: 50,
+            ..Default::default()
+        }
+    }
+
+    pub fn subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn exec(mut self, exec: impl Into<String>) -> Self {
+        self.exec = Some(exec.into());
+        self
+    }
+
+    pub fn score(mut self, score: u8) -> Self {
+        self.score = score.min(100);
+        self
+    }
+
+    /// Hands the item over to lanch through `push`
+    pub fn push_to(&self, push: PushFn, ctx: *mut c_void) {
+        // interior nul bytes can't be represented, such strings are dropped
+        let c = |s: &Option<String>| s.as_deref().and_then(|s| CString::new(s).ok());
+        let title = CString::new(self.title.as_str()).unwrap_or_default();
+        let (subtitle, icon, exec) = (c(&self.subtitle), c(&self.icon), c(&self.exec));
+        let ptr = |s: &Option<CString>| s.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
+
+        let raw = RawItem {
+            title: title.as_ptr(),
+            subtitle: ptr(&subtitle),
+            icon: ptr(&icon),
+            exec: ptr(&exec),
+            score: self.score,
+        };
+        push(ctx, &raw);
+    }
+}
+
+/// What a plugin has to implement, [`export_plugin`] takes care of the C side
+pub trait Plugin: Send + Sync {
+    /// Identifies the plugin in the config, read from a default instance before `init`
+    fn name(&self) -> &str;
+
+    fn description(&self) -> &str {
+        ""
+    }
+
+    /// Gets the module's config section as a JSON object
+    fn init(&mut self, _settings: &str) {}
+
+    fn get_matches(&self, query: &str, out: &mut Vec<Item>);
+
+    fn shutdown(&mut self) {}
+}
+
+/// Turns a C string into a `&str`, null and invalid UTF-8 become empty strings
+///
+/// # Safety
+/// `s` has to be null or point to a nul terminated string outliving `'a`
+pub unsafe fn str_from_ptr<'a>(s: *const c_char) -> &'a str {
+    if s.is_null() {
+        return "";
+    }
+    CStr::from_ptr(s).to_str().unwrap_or("")
+}
+
+/// Exports a type implementing [`Plugin`] and [`Default`] as a lanch plugin
+#[macro_export]
+macro_rules! export_plugin {
+    ($plugin:ty) => {
+        mod __lanch_plugin {
+            use super::*;
+            use std::ffi::{c_char, c_void, CString};
+            use std::sync::OnceLock;
+
+            static NAME: OnceLock<CString> = OnceLock::new();
+
+            struct State {
+                plugin: $plugin,
+                description: CString,
+            }
+
+            extern "C" fn init(settings: *const c_char) -> *mut c_void {
+                let mut plugin = <$plugin>::default();
+                $crate::Plugin::init(&mut plugin, unsafe { $crate::str_from_ptr(settings) });
+
+                let state = State {
+                    description: CString::new($crate::Plugin::description(&plugin))
+                        .unwrap_or_default(),
+                    plugin,
+                };
+                Box::into_raw(Box::new(state)) as *mut c_void
+            }
+
+            extern "C" fn name() -> *const c_char {
+                NAME.get_or_init(|| {
+                    CString::new($crate::Plugin::name(&<$plugin>::default())).unwrap_or_default()
+                })
+                .as_ptr()
+            }
+
+            extern "C" fn description(state: *const c_void) -> *const c_char {
+                unsafe { &*(state as *const State) }.description.as_ptr()
+            }
+
+            extern "C" fn get_matches(
+                state: *const c_void,
+                query: *const c_char,
+                push: $crate::PushFn,
+                ctx: *mut c_void,
+            ) {
+                let state = unsafe { &*(state as *const State) };
+                let mut items = Vec::new();
+                let query = unsafe { $crate::str_from_ptr(query) };
+                $crate::Plugin::get_matches(&state.plugin, query, &mut items);
+                items.iter().for_each(|item| item.push_to(push, ctx));
+            }
+
+            extern "C" fn shutdown(state: *mut c_void) {
+                let mut state = unsafe { Box::from_raw(state as *mut State) };
+                $crate::Plugin::shutdown(&mut state.plugin);
+            }
+
+            #[no_mangle]
+            pub static lanch_plugin: $crate::PluginVTable = $crate::PluginVTable {
+                abi_version: $crate::ABI_VERSION,
+                init,
+                name,
+                description,
+                get_matches,
+                shutdown,
+            };
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    // the settings of the plugins that were shut down
+    static SHUT_DOWN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[derive(Default)]
+    struct Greeter {
+        greeting: String,
+    }
+
+    impl Plugin for Greeter {
+        fn name(&self) -> &str {
+            "greeter"
+        }
+
+        fn description(&self) -> &str {
+            "Says hello"
+        }
+
+        fn init(&mut self, settings: &str) {
+            self.greeting = settings.to_string();
+        }
+
+        fn get_matches(&self, query: &str, out: &mut Vec<Item>) {
+            out.push(
+                Item::new(format!("{} {}", self.greeting, query))
+                    .exec("true")
+                    .score(250),
+            );
+            out.push(Item::new("nul\0byte").subtitle("nul\0byte"));
+        }
+
+        fn shutdown(&mut self) {
+            SHUT_DOWN.lock().unwrap().push(self.greeting.clone());
+        }
+    }
+
+    export_plugin!(Greeter);
+
+    extern "C" fn collect(ctx: *mut c_void, item: *const RawItem) {
+        let items = unsafe { &mut *(ctx as *mut Vec<Item>) };
+        let item = unsafe { &*item };
+        let optional =
+            |s: *const c_char| (!s.is_null()).then(|| unsafe { str_from_ptr(s) }.to_string());
+        items.push(Item {
+            title: unsafe { str_from_ptr(item.title) }.to_string(),
+            subtitle: optional(item.subtitle),
+            icon: optional(item.icon),
+            exec: optional(item.exec),
+            score: item.score,
+        });
+    }
+
+    #[test]
+    fn exported_vtable() {
+        let vtable = &__lanch_plugin::lanch_plugin;
+        assert_eq!(vtable.abi_version, ABI_VERSION);
+
+        // known before there is any state, lanch looks the config section up by it
+        assert_eq!(unsafe { str_from_ptr((vtable.name)()) }, "greeter");
+
+        let settings = CString::new("hi").unwrap();
+        let state = (vtable.init)(settings.as_ptr());
+        assert!(!state.is_null());
+        assert_eq!(
+            unsafe { str_from_ptr((vtable.description)(state)) },
+            "Says hello"
+        );
+
+        let query = CString::new("there").unwrap();
+        let mut items: Vec<Item> = Vec::new();
+        (vtable.get_matches)(
+            state,
+            query.as_ptr(),
+            collect,
+            &mut items as *mut Vec<Item> as *mut c_void,
+        );
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].title, "hi there");
+        assert_eq!(items[0].exec.as_deref(), Some("true"));
+        assert_eq!(items[0].icon, None);
+        assert_eq!(items[0].score, 100);
+        // strings with nul bytes can't cross, titles become empty and the rest is left out
+        assert_eq!(items[1].title, "");
+        assert_eq!(items[1].subtitle, None);
+
+        (vtable.shutdown)(state);
+        assert!(SHUT_DOWN.lock().unwrap().contains(&String::from("hi")));
+    }
+
+    #[test]
+    fn null_strings() {
+        assert_eq!(unsafe { str_from_ptr(std::ptr::null()) }, "");
+        let invalid = CString::new(vec![0xff, 0xfe]).unwrap();
+        assert_eq!(unsafe { str_from_ptr(invalid.as_ptr()) }, "");
+    }
+}
@@ -1,54 +1,129 @@
+use super::config::{data_dirs, data_home};
+use super::icons::IconTheme;
 use super::suggestion::executable::{ExecutableSuggestion, ProgramSuggestion};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::env;
 use std::fs::{self, File};
 use std::io::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use std::rc::Rc;
+use std::sync::{Arc, RwLock};
 
 lazy_static::lazy_static! {
     static ref CACHE_FILE_PATH: PathBuf = PathBuf::from(format!("{}/.cache/lanch/cachefile", env::var("HOME").unwrap()));
 }
 
+// bumped whenever the layout of the cache changes, so older cache files get regenerated
+const CACHE_VERSION: u64 = 4;
+
+/// The cache as loaded by the programs module, shared with the modules that need to know about
+/// the installed programs
+pub type SharedCache = Arc<RwLock<LanchCacheArc>>;
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct LanchCache {
-    // Programs are applications found in /usr/share/applications
+    version: u64,
+
+    // the icon theme the program icons were looked up in
+    icon_theme: Option<String>,
+    icon_scale: u16,
+
+    // Programs are the desktop entries found in the applications directories
     pub programs: Vec<ProgramSuggestion>,
 
     // files in $PATH
     pub executables: Vec<ExecutableSuggestion>,
 }
 
-pub struct LanchCacheRc {
-    // Programs are applications found in /usr/share/applications
-    pub programs: Vec<Rc<ProgramSuggestion>>,
+#[derive(Default)]
+pub struct LanchCacheArc {
+    // Programs are the desktop entries found in the applications directories
+    pub programs: Vec<Arc<ProgramSuggestion>>,
 
     // files in $PATH
-    pub executables: Vec<Rc<ExecutableSuggestion>>,
+    pub executables: Vec<Arc<ExecutableSuggestion>>,
 }
 
-impl From<LanchCache> for LanchCacheRc {
+impl From<LanchCache> for LanchCacheArc {
     fn from(value: LanchCache) -> Self {
         Self {
-            programs: value.programs.into_iter().map(Rc::new).collect(),
-            executables: value.executables.into_iter().map(Rc::new).collect(),
+            programs: value.programs.into_iter().map(Arc::new).collect(),
+            executables: value.executables.into_iter().map(Arc::new).collect(),
         }
     }
 }
 
 impl LanchCache {
-    fn generate_programs(
-        icon_theme: Option<&str>,
-    ) -> Result<Vec<ProgramSuggestion>, std::io::Error> {
+    // desktop entries along with their ids, entries in subdirectories get the directory as a
+    // prefix ("kde4/okular.desktop" is "kde4-okular.desktop")
+    fn desktop_entries(
+        dir: &Path,
+        prefix: &str,
+        entries: &mut Vec<(String, PathBuf)>,
+        visited: &mut HashSet<PathBuf>,
+    ) {
+        // symlinks can lead back to a directory further up, which would never end
+        match dir.canonicalize() {
+            Ok(canonical) if !visited.contains(&canonical) => visited.insert(canonical),
+            _ => return,
+        };
+
+        let dir = match fs::read_dir(dir) {
+            Ok(dir) => dir,
+            Err(_) => return,
+        };
+
+        for entry in dir.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+
+            if path.is_dir() {
+                Self::desktop_entries(&path, &format!("{}{}-", prefix, name), entries, visited);
+            } else if name.ends_with(".desktop") {
+                entries.push((format!("{}{}", prefix, name), path));
+            }
+        }
+    }
+
+    fn generate_programs(icon_theme: &IconTheme) -> Result<Vec<ProgramSuggestion>, std::io::Error> {
         let mut ret: Vec<ProgramSuggestion> = Vec::new();
 
-        for entry in fs::read_dir("/usr/share/applications")? {
-            let file = fs::read_to_string(entry?.path())?;
+        // the user's entries come first and shadow system entries with the same id
+        let mut entries = Vec::new();
+        let mut visited = HashSet::new();
+        for dir in std::iter::once(data_home()).chain(data_dirs()) {
+            Self::desktop_entries(&dir.join("applications"), "", &mut entries, &mut visited);
+        }
+        let mut seen = HashSet::new();
+
+        for (id, path) in entries {
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+            let file = match fs::read_to_string(&path) {
+                Ok(file) => file,
+                Err(_) => continue,
+            };
             let mut fields: [&str; 3] = ["", "", ""];
+            let mut mime_types = Vec::new();
+            let mut hidden = false;
 
             for line in file.lines() {
-                if line.starts_with("Name=") {
+                // only the main group describes the program, actions come after it
+                if line.starts_with('[') && line != "[Desktop Entry]" {
+                    break;
+                }
+
+                if line == "Hidden=true" {
+                    hidden = true;
+                } else if let Some(types) = line.strip_prefix("MimeType=") {
+                    mime_types = types
+                        .split(';')
+                        .filter(|t| !t.is_empty())
+                        .map(String::from)
+                        .collect();
+                } else if line.starts_with("Name=") {
                     let (_, val) = line.split_once('=').unwrap_or(("", ""));
 
                     if !val.is_empty() {
@@ -67,22 +142,18 @@ impl LanchCache {
                         fields[2] = val;
                     }
                 }
+            }
 
-                if fields.iter().all(|e| !e.is_empty()) {
-                    break;
-                }
+            // hidden entries are how users delete system entries
+            if hidden {
+                continue;
             }
 
-            let icon_path = if let Some(theme) = icon_theme {
-                freedesktop_icons::lookup(fields[2])
-                    .with_size(48)
-                    .with_theme(theme)
-                    .find()
-            } else {
-                freedesktop_icons::lookup(fields[2]).with_size(48).find()
-            };
+            let icon_path = icon_theme.lookup(fields[2]);
 
-            ret.push(ProgramSuggestion::new(fields[0], fields[1], icon_path));
+            ret.push(ProgramSuggestion::new(
+                &id, fields[0], fields[1], icon_path, mime_types,
+            ));
         }
 
         Ok(ret)
@@ -112,7 +183,7 @@ impl LanchCache {
     }
 
     // Generates new cache and writes it to disk
-    pub fn new(icon_theme: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(icon_theme: &IconTheme) -> Result<Self, Box<dyn std::error::Error>> {
         println!(
             "[CACHE] generating new cache at {:?}",
             CACHE_FILE_PATH.clone()
@@ -125,6 +196,9 @@ impl LanchCache {
         }
 
         let cache = Self {
+            version: CACHE_VERSION,
+            icon_theme: icon_theme.name.clone(),
+            icon_scale: icon_theme.scale,
             programs: Self::generate_programs(icon_theme)?,
             executables: Self::generate_executables()?,
         };
@@ -139,13 +213,21 @@ impl LanchCache {
         Ok(cache)
     }
 
-    pub fn from_disk_or_new(icon_theme: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn from_disk_or_new(icon_theme: &IconTheme) -> Result<Self, Box<dyn std::error::Error>> {
         let data = fs::read(CACHE_FILE_PATH.clone());
         match data {
-            Ok(data) => {
-                let decoded: LanchCache = bincode::deserialize(&data[..])?;
+            Ok(data) => match bincode::deserialize::<LanchCache>(&data[..]) {
                 Ok(decoded)
-            }
+                    if decoded.version == CACHE_VERSION
+                        && decoded.icon_theme == icon_theme.name
+                        && decoded.icon_scale == icon_theme.scale =>
+                {
+                    Ok(decoded)
+                }
+                // written by an older version with a different layout, or the icons come from
+                // another theme or size now
+                _ => Self::new(icon_theme),
+            },
             Err(_) => Self::new(icon_theme),
         }
     }
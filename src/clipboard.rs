@@ -0,0 +1,72 @@
+//! Copying text on behalf of the user, shared by every suggestion that has a value to copy
+
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::launcher;
+
+// programs that keep serving the clipboard in the background once lanch has exited. wl-copy
+// and xclip fork into the background by themselves after reading the text.
+const WAYLAND_HELPERS: [(&str, &[&str]); 1] = [("wl-copy", &[])];
+const X11_HELPERS: [(&str, &[&str]); 2] = [
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// How some text ended up on the clipboard
+pub enum Copied {
+    // a helper program owns the clipboard now and keeps it after lanch exits
+    Helper,
+
+    // no helper is installed, the window owns the clipboard until lanch exits unless a
+    // clipboard manager picks it up
+    Window,
+}
+
+// the helpers for the running session, Wayland first as XWayland sets $DISPLAY as well
+fn helpers() -> Vec<(&'static str, &'static [&'static str])> {
+    let mut helpers = Vec::new();
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        helpers.extend(WAYLAND_HELPERS);
+    }
+    if env::var_os("DISPLAY").is_some() {
+        helpers.extend(X11_HELPERS);
+    }
+    helpers
+}
+
+/// Puts `text` on the clipboard through the first installed helper. When there is none, the
+/// caller has to put it there through the window, which is what `Copied::Window` means.
+pub fn copy(text: &str) -> Result<Copied, Box<dyn std::error::Error>> {
+    let (program, args) = match helpers()
+        .into_iter()
+        .find(|(program, _)| launcher::in_path(program))
+    {
+        Some(helper) => helper,
+        None => return Ok(Copied::Window),
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+
+    // closing stdin tells the helper the text is complete
+    child
+        .stdin
+        .take()
+        .ok_or("no stdin")?
+        .write_all(text.as_bytes())
+        .map_err(|e| format!("failed to pass the text to {}: {}", program, e))?;
+
+    // returns as soon as the helper has forked into the background
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(format!("{} failed ({})", program, status).into());
+    }
+    Ok(Copied::Helper)
+}
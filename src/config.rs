@@ -0,0 +1,103 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+lazy_static::lazy_static! {
+    pub static ref CONFIG_DIR: PathBuf = config_home().join("lanch");
+    static ref CONFIG_FILE_PATH: PathBuf = CONFIG_DIR.join("config.toml");
+}
+
+/// `$XDG_CONFIG_HOME`, falling back to `~/.config`
+pub fn config_home() -> PathBuf {
+    match env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(format!("{}/.config", env::var("HOME").unwrap())),
+    }
+}
+
+/// `$XDG_DATA_HOME`, falling back to `~/.local/share`
+pub fn data_home() -> PathBuf {
+    match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(format!("{}/.local/share", env::var("HOME").unwrap())),
+    }
+}
+
+/// `$XDG_CONFIG_DIRS`, falling back to `/etc/xdg`
+pub fn config_dirs() -> Vec<PathBuf> {
+    xdg_dirs("XDG_CONFIG_DIRS", "/etc/xdg")
+}
+
+/// `$XDG_DATA_DIRS`, falling back to `/usr/local/share` and `/usr/share`
+pub fn data_dirs() -> Vec<PathBuf> {
+    xdg_dirs("XDG_DATA_DIRS", "/usr/local/share:/usr/share")
+}
+
+fn xdg_dirs(var: &str, default: &str) -> Vec<PathBuf> {
+    let dirs = match env::var(var) {
+        Ok(dirs) if !dirs.is_empty() => dirs,
+        _ => default.to_string(),
+    };
+    env::split_paths(&dirs).collect()
+}
+
+/// User configuration, read from `~/.config/lanch/config.toml`.
+/// Every field is optional, missing ones fall back to their defaults.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LanchConfig {
+    pub icons: IconConfig,
+
+    // per module settings, keyed by the module name
+    pub modules: HashMap<String, ModuleConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct IconConfig {
+    // overrides the detected icon theme
+    pub theme: Option<String>,
+
+    // overrides the detected HiDPI scale factor
+    pub scale: Option<u16>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ModuleConfig {
+    // overrides the prefix or keyword routing queries to the module, empty to remove it
+    pub trigger: Option<String>,
+
+    // overrides whether the module is queried for untriggered queries
+    pub global: Option<bool>,
+
+    // disabled modules are loaded but never queried
+    pub enabled: Option<bool>,
+
+    // everything else in the section, handed to the module itself
+    #[serde(flatten)]
+    pub settings: toml::Table,
+}
+
+impl LanchConfig {
+    pub fn load() -> Self {
+        let data = match fs::read_to_string(CONFIG_FILE_PATH.as_path()) {
+            Ok(data) => data,
+            Err(_) => return Self::default(),
+        };
+
+        match toml::from_str(&data) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!(
+                    "[CONFIG] failed to parse {:?}: {}",
+                    CONFIG_FILE_PATH.as_path(),
+                    e
+                );
+                Self::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::config::{config_home, IconConfig};
+
+// size (in px) of the icons looked up for programs, before scaling
+const ICON_SIZE: u16 = 48;
+
+/// The icon theme and scale used when resolving program icons
+#[derive(Debug, Clone)]
+pub struct IconTheme {
+    pub name: Option<String>,
+    pub scale: u16,
+}
+
+impl IconTheme {
+    /// Uses the values from the config where present, detects the rest from the desktop
+    /// environment settings
+    pub fn detect(config: &IconConfig) -> Self {
+        let name = config.theme.clone().or_else(detect_theme_name);
+        let scale = config.scale.unwrap_or_else(detect_scale).max(1);
+
+        println!("[ICONS] using theme {:?} at scale {}", name, scale);
+
+        Self { name, scale }
+    }
+
+    pub fn lookup(&self, icon: &str) -> Option<PathBuf> {
+        // themes that ship scaled directories are matched directly, the rest get looked up at
+        // the physical size instead
+        self.lookup_sized(icon, ICON_SIZE, self.scale)
+            .or_else(|| self.lookup_sized(icon, ICON_SIZE * self.scale, 1))
+    }
+
+    fn lookup_sized(&self, icon: &str, size: u16, scale: u16) -> Option<PathBuf> {
+        let lookup = freedesktop_icons::lookup(icon)
+            .with_size(size)
+            .with_scale(scale);
+
+        if let Some(theme) = &self.name {
+            lookup.with_theme(theme).find()
+        } else {
+            lookup.find()
+        }
+    }
+}
+
+fn is_kde() -> bool {
+    env::var("XDG_CURRENT_DESKTOP")
+        .map(|desktop| desktop.split(':').any(|d| d.eq_ignore_ascii_case("kde")))
+        .unwrap_or(false)
+}
+
+fn detect_theme_name() -> Option<String> {
+    let sources: [fn() -> Option<String>; 4] = if is_kde() {
+        [
+            from_kdeglobals,
+            from_gsettings,
+            from_gtk_settings,
+            from_xsettingsd,
+        ]
+    } else {
+        [
+            from_gsettings,
+            from_gtk_settings,
+            from_xsettingsd,
+            from_kdeglobals,
+        ]
+    };
+
+    sources.iter().find_map(|source| source())
+}
+
+// `key = value` lookup inside an ini `[section]`
+fn ini_value(data: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+
+    for line in data.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == format!("[{section}]");
+        } else if in_section {
+            if let Some((k, v)) = line.split_once('=') {
+                if k.trim() == key && !v.trim().is_empty() {
+                    return Some(v.trim().to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+fn from_gtk_settings() -> Option<String> {
+    ["gtk-4.0", "gtk-3.0"].iter().find_map(|dir| {
+        let data = fs::read_to_string(config_home().join(dir).join("settings.ini")).ok()?;
+        ini_value(&data, "Settings", "gtk-icon-theme-name")
+    })
+}
+
+fn from_gsettings() -> Option<String> {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let theme = String::from_utf8(output.stdout).ok()?;
+    let theme = theme.trim().trim_matches('\'');
+    (!theme.is_empty()).then(|| theme.to_string())
+}
+
+fn from_xsettingsd() -> Option<String> {
+    let paths = [
+        config_home().join("xsettingsd/xsettingsd.conf"),
+        PathBuf::from(format!("{}/.xsettingsd", env::var("HOME").unwrap())),
+    ];
+
+    paths.iter().find_map(|path| {
+        let data = fs::read_to_string(path).ok()?;
+        data.lines().find_map(|line| {
+            let value = line.trim().strip_prefix("Net/IconThemeName")?;
+            let value = value.trim().trim_matches('"');
+            (!value.is_empty()).then(|| value.to_string())
+        })
+    })
+}
+
+fn from_kdeglobals() -> Option<String> {
+    let data = fs::read_to_string(config_home().join("kdeglobals")).ok()?;
+    ini_value(&data, "Icons", "Theme")
+}
+
+fn detect_scale() -> u16 {
+    let from_env = |var: &str| {
+        env::var(var)
+            .ok()
+            .and_then(|v| v.trim().parse::<f32>().ok())
+            .map(|v| v.round() as u16)
+            .filter(|v| *v > 0)
+    };
+
+    from_env("GDK_SCALE")
+        .or_else(|| from_env("QT_SCALE_FACTOR"))
+        .or_else(|| {
+            let output = Command::new("gsettings")
+                .args(["get", "org.gnome.desktop.interface", "scaling-factor"])
+                .output()
+                .ok()?;
+
+            // gsettings prints "uint32 N", where 0 means automatic
+            let out = String::from_utf8(output.stdout).ok()?;
+            out.split_whitespace()
+                .last()?
+                .parse::<u16>()
+                .ok()
+                .filter(|v| *v > 0)
+        })
+        .unwrap_or(1)
+}
@@ -0,0 +1,134 @@
+//! Starting programs on behalf of the user, shared by every module that launches something
+
+use std::env;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+// tried in order when `$TERMINAL` isn't set
+const TERMINALS: [&str; 10] = [
+    "x-terminal-emulator",
+    "kgx",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "alacritty",
+    "kitty",
+    "foot",
+    "wezterm",
+    "xterm",
+];
+
+/// Splits an `Exec` style command line into its arguments. Quotes and backslash escapes are
+/// honored, the file and url field codes (`%f`, `%u`...) are replaced by `target` and every
+/// other field code is dropped.
+pub fn split_exec(exec: &str, target: Option<&str>) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut quote = None;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, quote) {
+            ('\\', Some('\'')) => arg.push(c),
+            ('\\', _) => arg.extend(chars.next()),
+            ('"' | '\'', None) => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (c, None) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            (c, _) => {
+                arg.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(arg);
+    }
+
+    let mut expanded = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "%f" | "%F" | "%u" | "%U" => expanded.extend(target.map(String::from)),
+            "%%" => expanded.push(String::from("%")),
+            code if code.len() == 2 && code.starts_with('%') => {}
+            _ => expanded.push(arg.replace("%%", "%")),
+        }
+    }
+    expanded
+}
+
+/// Starts a program without tying it to lanch, so it keeps running after lanch exits
+pub fn spawn(argv: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (program, args) = argv.split_first().ok_or("empty command")?;
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+
+    // reaps the child once it's done, lanch usually exits long before that
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Runs a command line, starting a program directly or going through `sh` if the line needs a
+/// shell (pipes, redirections, variables...)
+pub fn run(cmd: &str, terminal: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let argv = if cmd.contains(|c| "|&;<>()$`*?~".contains(c)) {
+        vec!["sh".to_string(), "-c".to_string(), cmd.to_string()]
+    } else {
+        split_exec(cmd, None)
+    };
+
+    if terminal {
+        spawn(&in_terminal(argv)?)
+    } else {
+        spawn(&argv)
+    }
+}
+
+/// Opens a file or url with the application the user picked for it
+pub fn open(target: &str) -> Result<(), Box<dyn std::error::Error>> {
+    spawn(&["xdg-open".to_string(), target.to_string()])
+}
+
+/// Wraps a command so it runs inside the user's terminal emulator
+pub fn in_terminal(argv: Vec<String>) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let terminal = match env::var("TERMINAL") {
+        Ok(terminal) if !terminal.is_empty() => terminal,
+        _ => TERMINALS
+            .iter()
+            .find(|t| in_path(t))
+            .ok_or("no terminal emulator found, set $TERMINAL")?
+            .to_string(),
+    };
+
+    // most terminals take the command after `-e`, the rest need something else
+    let separator: &[&str] = match Path::new(&terminal).file_name().and_then(|n| n.to_str()) {
+        Some("gnome-terminal") => &["--"],
+        Some("wezterm") => &["start", "--"],
+        Some("kitty" | "foot") => &[],
+        _ => &["-e"],
+    };
+
+    let mut wrapped = vec![terminal];
+    wrapped.extend(separator.iter().map(|s| s.to_string()));
+    wrapped.extend(argv);
+    Ok(wrapped)
+}
+
+/// Whether `program` can be found in `$PATH`
+pub fn in_path(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
@@ -1,9 +1,29 @@
+use std::env;
+use std::process;
+
 mod cache;
+mod clipboard;
+mod config;
+mod icons;
+mod launcher;
+mod mime;
 mod suggestion;
 mod ui;
 
 fn main() -> Result<(), iced::Error> {
-    // TODO: user config
+    let config = config::LanchConfig::load();
+
+    // meant to be scheduled (cron, systemd timers...), searching never goes online
+    if env::args().nth(1).as_deref() == Some("--refresh-rates") {
+        match suggestion::currency::refresh_rates(&config) {
+            Ok(path) => println!("exchange rates saved to {}", path.display()),
+            Err(e) => {
+                eprintln!("failed to refresh the exchange rates: {}", e);
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
 
-    ui::init()
+    ui::init(config)
 }
@@ -0,0 +1,825 @@
+//! File types and the programs that open them, following the shared-mime-info and the mime apps
+//! specifications. Modules dealing with files use this to find the default and alternative
+//! programs for them.
+
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::config::{config_dirs, config_home, data_dirs, data_home};
+use crate::suggestion::executable::ProgramSuggestion;
+
+lazy_static::lazy_static! {
+    static ref DATABASE: MimeDatabase = MimeDatabase::load();
+
+    // the user changes defaults more often than the database changes, so these get reloaded
+    static ref MIMEAPPS: RwLock<Vec<MimeApps>> = RwLock::new(mimeapps_lists());
+}
+
+const OCTET_STREAM: &str = "application/octet-stream";
+const TEXT_PLAIN: &str = "text/plain";
+
+// files larger than this are only sniffed at the start
+const MAX_SNIFF_LEN: usize = 64 * 1024;
+
+/// A pattern of a `globs2` file
+struct Glob {
+    weight: u32,
+    mime: String,
+    pattern: String,
+    case_sensitive: bool,
+}
+
+impl Glob {
+    // the name has to be lowercased already unless the glob is case sensitive
+    fn matches(&self, name: &str, name_lower: &str) -> bool {
+        let name = if self.case_sensitive {
+            name
+        } else {
+            name_lower
+        };
+        let pattern: Vec<char> = self.pattern.chars().collect();
+        let name: Vec<char> = name.chars().collect();
+        glob_match(&pattern, &name)
+    }
+
+    fn is_literal(&self) -> bool {
+        !self.pattern.contains(['*', '?', '['])
+    }
+
+    // literal names beat patterns, then the weight decides, then case sensitive globs and
+    // finally the longer pattern ("*.tar.gz" over "*.gz")
+    fn rank(&self) -> (bool, u32, bool, usize) {
+        (
+            self.is_literal(),
+            self.weight,
+            self.case_sensitive,
+            self.pattern.len(),
+        )
+    }
+}
+
+// fnmatch without the flags, enough for the patterns in the mime database
+fn glob_match(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| glob_match(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && glob_match(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let end = match pattern.iter().position(|c| *c == ']') {
+                Some(end) => end,
+                None => return false,
+            };
+            let (negated, class) = match pattern[1] {
+                '!' | '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+            let c = match name.first() {
+                Some(c) => *c,
+                None => return false,
+            };
+
+            let mut matched = false;
+            let mut i = 0;
+            while i < class.len() {
+                if i + 2 < class.len() && class[i + 1] == '-' {
+                    matched |= (class[i]..=class[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    matched |= class[i] == c;
+                    i += 1;
+                }
+            }
+
+            matched != negated && glob_match(&pattern[end + 1..], &name[1..])
+        }
+        Some(p) => name.first() == Some(p) && glob_match(&pattern[1..], &name[1..]),
+    }
+}
+
+/// A line of a `magic` file, along with the lines nested below it
+#[derive(Debug, Default)]
+struct Matchlet {
+    offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    range: usize,
+    children: Vec<Matchlet>,
+}
+
+impl Matchlet {
+    fn matches(&self, data: &[u8]) -> bool {
+        let found = (self.offset..self.offset + self.range).any(|start| {
+            let bytes = match data.get(start..start + self.value.len()) {
+                Some(bytes) => bytes,
+                None => return false,
+            };
+            match &self.mask {
+                Some(mask) => bytes
+                    .iter()
+                    .zip(&self.value)
+                    .zip(mask)
+                    .all(|((b, v), m)| b & m == v & m),
+                None => bytes == self.value.as_slice(),
+            }
+        });
+
+        found && (self.children.is_empty() || self.children.iter().any(|c| c.matches(data)))
+    }
+
+    // how far into a file this has to look
+    fn extent(&self) -> usize {
+        let own = self.offset + self.range + self.value.len();
+        self.children
+            .iter()
+            .map(Matchlet::extent)
+            .fold(own, usize::max)
+    }
+}
+
+struct MagicRule {
+    priority: u32,
+    mime: String,
+    matchlets: Vec<Matchlet>,
+}
+
+// parses the binary magic format, see the shared-mime-info spec
+fn parse_magic(data: &[u8]) -> Option<Vec<MagicRule>> {
+    let mut data = data.strip_prefix(b"MIME-Magic\0\n")?;
+    let mut rules = Vec::new();
+
+    while !data.is_empty() {
+        // section header: [priority:mime/type]
+        let end = data.iter().position(|b| *b == b'\n')?;
+        let header = std::str::from_utf8(&data[..end]).ok()?;
+        let (priority, mime) = header
+            .strip_prefix('[')?
+            .strip_suffix(']')?
+            .split_once(':')?;
+        data = &data[end + 1..];
+
+        let mut rule = MagicRule {
+            priority: priority.parse().ok()?,
+            mime: mime.to_string(),
+            matchlets: Vec::new(),
+        };
+
+        // the matchlets, each line starting with its nesting level
+        while !data.is_empty() && data[0] != b'[' {
+            let (indent, rest) = take_number(data);
+            let (offset, rest) = take_number(rest.strip_prefix(b">")?);
+            let rest = rest.strip_prefix(b"=")?;
+            let len = u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize;
+            let mut matchlet = Matchlet {
+                offset: offset.unwrap_or(0),
+                value: rest.get(2..2 + len)?.to_vec(),
+                range: 1,
+                ..Default::default()
+            };
+            let mut rest = &rest[2 + len..];
+
+            if let Some(masked) = rest.strip_prefix(b"&") {
+                matchlet.mask = Some(masked.get(..len)?.to_vec());
+                rest = &masked[len..];
+            }
+            let mut word_size = 1;
+            if let Some(word) = rest.strip_prefix(b"~") {
+                let (size, after) = take_number(word);
+                word_size = size.unwrap_or(1);
+                rest = after;
+            }
+            if let Some(range) = rest.strip_prefix(b"+") {
+                let (range, after) = take_number(range);
+                matchlet.range = range.unwrap_or(1).max(1);
+                rest = after;
+            }
+            data = rest.strip_prefix(b"\n")?;
+
+            // values of multi byte words are stored big endian
+            if word_size > 1 && cfg!(target_endian = "little") {
+                matchlet
+                    .value
+                    .chunks_mut(word_size)
+                    .for_each(|w| w.reverse());
+                if let Some(mask) = &mut matchlet.mask {
+                    mask.chunks_mut(word_size).for_each(|w| w.reverse());
+                }
+            }
+
+            // nested matchlets hang below the last one of the level above
+            let mut siblings = &mut rule.matchlets;
+            for _ in 0..indent.unwrap_or(0) {
+                siblings = &mut siblings.last_mut()?.children;
+            }
+            siblings.push(matchlet);
+        }
+
+        rules.push(rule);
+    }
+
+    Some(rules)
+}
+
+// a decimal number at the start of `data`, if there is one
+fn take_number(data: &[u8]) -> (Option<usize>, &[u8]) {
+    let len = data.iter().take_while(|b| b.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&data[..len])
+        .ok()
+        .and_then(|n| n.parse().ok());
+    (number, &data[len..])
+}
+
+/// The shared-mime-info database, merged from every data directory
+#[derive(Default)]
+struct MimeDatabase {
+    globs: Vec<Glob>,
+
+    // sorted by priority, highest first
+    magic: Vec<MagicRule>,
+
+    // alias -> canonical name
+    aliases: HashMap<String, String>,
+
+    // type -> the types it is a special case of
+    parents: HashMap<String, Vec<String>>,
+}
+
+impl MimeDatabase {
+    fn load() -> Self {
+        let mut db = Self::default();
+
+        // the user's database comes first, so it wins over the system one
+        for dir in std::iter::once(data_home()).chain(data_dirs()) {
+            let dir = dir.join("mime");
+
+            if let Ok(data) = fs::read_to_string(dir.join("globs2")) {
+                db.add_globs(&data);
+            }
+
+            if let Ok(data) = fs::read(dir.join("magic")) {
+                match parse_magic(&data) {
+                    Some(rules) => db.magic.extend(rules),
+                    None => eprintln!("[MIME] failed to parse {:?}", dir.join("magic")),
+                }
+            }
+
+            for (file, is_alias) in [("aliases", true), ("subclasses", false)] {
+                if let Ok(data) = fs::read_to_string(dir.join(file)) {
+                    db.add_relations(&data, is_alias);
+                }
+            }
+        }
+
+        db.magic
+            .sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+        db
+    }
+
+    // the lines of a globs2 file: "weight:type:pattern" with optional flags
+    fn add_globs(&mut self, data: &str) {
+        self.globs.extend(data.lines().filter_map(|line| {
+            if line.starts_with('#') {
+                return None;
+            }
+            let mut fields = line.split(':');
+            Some(Glob {
+                weight: fields.next()?.parse().unwrap_or(50),
+                mime: fields.next()?.to_string(),
+                pattern: fields.next()?.to_string(),
+                case_sensitive: fields.next().is_some_and(|flags| flags.contains("cs")),
+            })
+        }));
+    }
+
+    // the lines of an aliases or subclasses file, "child parent". Aliases from the directories
+    // read first win.
+    fn add_relations(&mut self, data: &str, is_alias: bool) {
+        for (child, parent) in data.lines().filter_map(|l| l.split_once(' ')) {
+            if is_alias {
+                self.aliases
+                    .entry(child.to_string())
+                    .or_insert_with(|| parent.to_string());
+            } else {
+                self.parents
+                    .entry(child.to_string())
+                    .or_default()
+                    .push(parent.to_string());
+            }
+        }
+    }
+
+    fn unalias<'a>(&'a self, mime: &'a str) -> &'a str {
+        self.aliases.get(mime).map_or(mime, String::as_str)
+    }
+
+    // the types of the best ranked globs matching the name
+    fn glob_matches(&self, name: &str) -> Vec<&str> {
+        let lower = name.to_lowercase();
+        let mut matches: Vec<&Glob> = self
+            .globs
+            .iter()
+            .filter(|glob| glob.matches(name, &lower))
+            .collect();
+        matches.sort_by_key(|glob| std::cmp::Reverse(glob.rank()));
+
+        let best = match matches.first() {
+            Some(glob) => glob.rank(),
+            None => return Vec::new(),
+        };
+        let mut types: Vec<&str> = Vec::new();
+        for glob in matches {
+            if glob.rank() != best {
+                break;
+            }
+            if !types.contains(&glob.mime.as_str()) {
+                types.push(&glob.mime);
+            }
+        }
+        types
+    }
+
+    fn magic_match(&self, data: &[u8]) -> Option<&str> {
+        self.magic
+            .iter()
+            .find(|rule| rule.matchlets.iter().any(|m| m.matches(data)))
+            .map(|rule| rule.mime.as_str())
+    }
+
+    fn sniff_len(&self) -> usize {
+        self.magic
+            .iter()
+            .flat_map(|rule| &rule.matchlets)
+            .map(Matchlet::extent)
+            .max()
+            .unwrap_or(0)
+            .min(MAX_SNIFF_LEN)
+    }
+
+    fn is_a(&self, mime: &str, ancestor: &str) -> bool {
+        let ancestor = self.unalias(ancestor);
+        self.ancestors(mime).iter().any(|m| m == ancestor)
+    }
+
+    // the type itself and everything it is a special case of, closest first
+    fn ancestors(&self, mime: &str) -> Vec<String> {
+        let mut ancestors = vec![self.unalias(mime).to_string()];
+        let mut i = 0;
+        while i < ancestors.len() {
+            let current = ancestors[i].clone();
+            let mut parents: Vec<String> = self.parents.get(&current).cloned().unwrap_or_default();
+            // implied by the spec without being listed
+            if current.starts_with("text/") && current != TEXT_PLAIN {
+                parents.push(TEXT_PLAIN.to_string());
+            }
+            for parent in parents {
+                let parent = self.unalias(&parent).to_string();
+                if !ancestors.contains(&parent) {
+                    ancestors.push(parent);
+                }
+            }
+            i += 1;
+        }
+        ancestors
+    }
+
+    fn for_path(&self, path: &Path) -> String {
+        if path.is_dir() {
+            return String::from("inode/directory");
+        }
+
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        let globbed = self.glob_matches(name);
+        if globbed.len() == 1 {
+            return self.unalias(globbed[0]).to_string();
+        }
+
+        // the name alone isn't conclusive, look at the content
+        let mut data = Vec::new();
+        if let Ok(file) = File::open(path) {
+            let _ = file.take(self.sniff_len() as u64).read_to_end(&mut data);
+        }
+        let sniffed = self.magic_match(&data);
+
+        // the content decides between several candidates for the name
+        if let Some(sniffed) = sniffed {
+            if let Some(mime) = globbed.iter().find(|m| self.is_a(sniffed, m)) {
+                return self.unalias(mime).to_string();
+            }
+        }
+        if let Some(mime) = globbed.first() {
+            return self.unalias(mime).to_string();
+        }
+
+        match sniffed {
+            Some(mime) => self.unalias(mime).to_string(),
+            None if looks_like_text(&data) => String::from(TEXT_PLAIN),
+            None => String::from(OCTET_STREAM),
+        }
+    }
+}
+
+fn looks_like_text(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(256)];
+    !head.contains(&0)
+        && match std::str::from_utf8(head) {
+            Ok(_) => true,
+            // cut off in the middle of a character
+            Err(e) => e.error_len().is_none(),
+        }
+}
+
+/// The mime type of a file, from its name and if that isn't enough, its content
+pub fn for_path(path: &Path) -> String {
+    DATABASE.for_path(path)
+}
+
+/// The pseudo mime type programs handling a url scheme register for
+pub fn for_url(url: &str) -> Option<String> {
+    let (scheme, _) = url.split_once(':')?;
+    Some(format!("x-scheme-handler/{}", scheme.to_lowercase()))
+}
+
+/// The type along with the types it is a special case of, closest first. A program opening any
+/// of them can open files of this type.
+pub fn ancestors(mime: &str) -> Vec<String> {
+    DATABASE.ancestors(mime)
+}
+
+/// A mimeapps.list file
+#[derive(Debug, Default, Clone)]
+struct MimeApps {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, Vec<String>>,
+}
+
+impl MimeApps {
+    fn parse(data: &str) -> Self {
+        let mut apps = Self::default();
+        let mut group = None;
+
+        for line in data.lines().map(str::trim) {
+            if line.starts_with('[') {
+                group = match line {
+                    "[Default Applications]" => Some(&mut apps.defaults),
+                    "[Added Associations]" => Some(&mut apps.added),
+                    "[Removed Associations]" => Some(&mut apps.removed),
+                    _ => None,
+                };
+                continue;
+            }
+
+            if let (Some(group), Some((mime, ids))) = (group.as_mut(), line.split_once('=')) {
+                group.entry(mime.trim().to_string()).or_default().extend(
+                    ids.split(';')
+                        .map(str::trim)
+                        .filter(|id| !id.is_empty())
+                        .map(String::from),
+                );
+            }
+        }
+
+        apps
+    }
+}
+
+// every mimeapps.list in the order they are consulted, desktop specific ones before the
+// general one in each directory
+fn mimeapps_lists() -> Vec<MimeApps> {
+    let desktops: Vec<String> = env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|d| !d.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+
+    let mut dirs = vec![config_home()];
+    dirs.extend(config_dirs());
+    dirs.push(data_home().join("applications"));
+    dirs.extend(data_dirs().into_iter().map(|d| d.join("applications")));
+
+    let mut lists = Vec::new();
+    for dir in dirs {
+        let names = desktops
+            .iter()
+            .map(|d| format!("{}-mimeapps.list", d))
+            .chain(std::iter::once(String::from("mimeapps.list")));
+        for name in names {
+            if let Ok(data) = fs::read_to_string(dir.join(name)) {
+                lists.push(MimeApps::parse(&data));
+            }
+        }
+    }
+    lists
+}
+
+/// Reads the mimeapps.list files again, after the user changed their default programs
+pub fn reload_mimeapps() {
+    *MIMEAPPS.write().unwrap() = mimeapps_lists();
+}
+
+/// The programs able to open a file type
+#[derive(Debug, Default)]
+pub struct Handlers {
+    // what the user picked in mimeapps.list, or else the most preferred of the others
+    pub default: Option<Arc<ProgramSuggestion>>,
+
+    // every other program associated with the type, the more specific associations first
+    pub others: Vec<Arc<ProgramSuggestion>>,
+}
+
+/// Looks up the programs opening `mime` among the installed `programs`
+pub fn handlers(mime: &str, programs: &[Arc<ProgramSuggestion>]) -> Handlers {
+    find_handlers(&MIMEAPPS.read().unwrap(), ancestors(mime), programs)
+}
+
+// `ancestors` are the type and the types it is a special case of, closest first
+fn find_handlers(
+    lists: &[MimeApps],
+    ancestors: Vec<String>,
+    programs: &[Arc<ProgramSuggestion>],
+) -> Handlers {
+    let installed = |id: &str| programs.iter().find(|p| p.id() == id);
+
+    let mut default = None;
+    let mut associated: Vec<Arc<ProgramSuggestion>> = Vec::new();
+
+    for mime in ancestors {
+        // the first installed default in the most important list wins
+        if default.is_none() {
+            default = lists
+                .iter()
+                .filter_map(|list| list.defaults.get(&mime))
+                .flatten()
+                .find_map(|id| installed(id))
+                .cloned();
+        }
+
+        // associations removed in a list only apply to the lists after it
+        let mut removed: HashSet<&str> = HashSet::new();
+        for list in lists.iter() {
+            for id in list.added.get(&mime).into_iter().flatten() {
+                if let Some(program) = installed(id).filter(|_| !removed.contains(id.as_str())) {
+                    associated.push(Arc::clone(program));
+                }
+            }
+            removed.extend(
+                list.removed
+                    .get(&mime)
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str),
+            );
+        }
+
+        associated.extend(
+            programs
+                .iter()
+                .filter(|p| p.mime_types().contains(&mime) && !removed.contains(p.id()))
+                .cloned(),
+        );
+    }
+
+    let mut seen = HashSet::new();
+    associated.retain(|p| seen.insert(p.id().to_string()));
+
+    let default = default.or_else(|| associated.first().cloned());
+    if let Some(default) = &default {
+        associated.retain(|p| !Arc::ptr_eq(p, default));
+    }
+
+    Handlers {
+        default,
+        others: associated,
+    }
+}
+
+/// Shorthand for the programs opening a file
+pub fn handlers_for_path(path: &Path, programs: &[Arc<ProgramSuggestion>]) -> Handlers {
+    handlers(&for_path(path), programs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GLOBS: &str = "\
+# comment
+50:application/gzip:*.gz
+50:application/x-compressed-tar:*.tar.gz
+50:text/x-csrc:*.c
+50:text/x-c++src:*.C:cs
+50:text/x-makefile:makefile
+10:text/x-makefile:*.mk
+50:text/plain:*.[tT][xX][tT]
+50:application/x-backup:*~
+";
+
+    const ALIASES: &str = "application/x-gzip application/gzip\n";
+    const SUBCLASSES: &str = "\
+application/x-compressed-tar application/gzip
+text/x-csrc text/plain
+";
+
+    const MAGIC: &[u8] = b"MIME-Magic\0\n\
+[80:image/png]\n\
+>0=\0\x04\x89PNG\n\
+[50:application/x-foo]\n\
+>0=\0\x03FOO\n\
+1>8=\0\x02ok\n\
+[40:application/x-bar]\n\
+>4=\0\x03BAR+8\n\
+[30:application/x-masked]\n\
+>0=\0\x02\xf0\0&\xf0\xf0\n";
+
+    fn database() -> MimeDatabase {
+        let mut db = MimeDatabase::default();
+        db.add_globs(GLOBS);
+        db.add_relations(ALIASES, true);
+        db.add_relations(SUBCLASSES, false);
+        db.magic = parse_magic(MAGIC).unwrap();
+        db
+    }
+
+    fn matches(pattern: &str, name: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let name: Vec<char> = name.chars().collect();
+        glob_match(&pattern, &name)
+    }
+
+    #[test]
+    fn globs() {
+        assert!(matches("*.gz", "a.tar.gz"));
+        assert!(matches("*", ""));
+        assert!(matches("?.c", "ä.c"));
+        assert!(matches("[a-c]x", "bx"));
+        assert!(matches("[!a-c]x", "dx"));
+        assert!(!matches("[!a-c]x", "ax"));
+        assert!(!matches("[abc", "a"));
+        assert!(!matches("*.gz", "a.gzip"));
+    }
+
+    #[test]
+    fn glob_ranking() {
+        let db = database();
+        assert_eq!(
+            db.glob_matches("backup.tar.gz"),
+            ["application/x-compressed-tar"]
+        );
+        assert_eq!(db.glob_matches("notes.gz"), ["application/gzip"]);
+        assert_eq!(db.glob_matches("NOTES.TXT"), ["text/plain"]);
+        assert_eq!(db.glob_matches("Makefile"), ["text/x-makefile"]);
+        assert_eq!(db.glob_matches("notes.txt~"), ["application/x-backup"]);
+        assert!(db.glob_matches("notes").is_empty());
+    }
+
+    #[test]
+    fn case_sensitive_globs() {
+        let db = database();
+        assert_eq!(db.glob_matches("main.C"), ["text/x-c++src"]);
+        assert_eq!(db.glob_matches("main.c"), ["text/x-csrc"]);
+    }
+
+    #[test]
+    fn magic() {
+        let db = database();
+        assert_eq!(db.magic.len(), 4);
+        assert_eq!(db.magic_match(b"\x89PNG\r\n"), Some("image/png"));
+
+        // nested matchlets have to match as well
+        assert_eq!(db.magic_match(b"FOO.....ok"), Some("application/x-foo"));
+        assert_eq!(db.magic_match(b"FOO.....no"), None);
+
+        // anywhere within the range
+        assert_eq!(db.magic_match(b"....BAR"), Some("application/x-bar"));
+        assert_eq!(db.magic_match(b"...........BAR"), Some("application/x-bar"));
+        assert_eq!(db.magic_match(b"............BAR"), None);
+
+        assert_eq!(db.magic_match(b"\xf5\x0a"), Some("application/x-masked"));
+        assert_eq!(db.magic_match(b"\x05\x0a"), None);
+
+        assert_eq!(db.sniff_len(), 4 + 8 + 3);
+    }
+
+    #[test]
+    fn broken_magic() {
+        assert!(parse_magic(b"not magic").is_none());
+        assert!(parse_magic(b"MIME-Magic\0\n[50:text/x-foo]\n>0=\0\x09short\n").is_none());
+        assert!(parse_magic(b"MIME-Magic\0\n[50:text/x-foo]\n1>0=\0\x01a\n").is_none());
+    }
+
+    #[test]
+    fn aliases_and_subclasses() {
+        let db = database();
+        assert_eq!(db.ancestors("application/x-gzip"), ["application/gzip"]);
+        assert_eq!(
+            db.ancestors("application/x-compressed-tar"),
+            ["application/x-compressed-tar", "application/gzip"]
+        );
+        assert_eq!(db.ancestors("text/x-csrc"), ["text/x-csrc", "text/plain"]);
+        // every text type is plain text
+        assert_eq!(
+            db.ancestors("text/x-makefile"),
+            ["text/x-makefile", "text/plain"]
+        );
+        assert!(db.is_a("application/x-compressed-tar", "application/x-gzip"));
+    }
+
+    #[test]
+    fn mimeapps_lists() {
+        let list = MimeApps::parse(
+            "[Default Applications]\n\
+             text/plain = editor.desktop;\n\
+             [Unknown Group]\n\
+             text/plain=ignored.desktop\n\
+             [Added Associations]\n\
+             text/plain=viewer.desktop;;other.desktop\n",
+        );
+        assert_eq!(list.defaults["text/plain"], ["editor.desktop"]);
+        assert_eq!(
+            list.added["text/plain"],
+            ["viewer.desktop", "other.desktop"]
+        );
+        assert!(list.removed.is_empty());
+    }
+
+    fn program(id: &str, mime_types: &[&str]) -> Arc<ProgramSuggestion> {
+        let mime_types = mime_types.iter().map(|m| m.to_string()).collect();
+        Arc::new(ProgramSuggestion::new(id, id, id, None, mime_types))
+    }
+
+    fn ids(handlers: &Handlers) -> (Option<&str>, Vec<&str>) {
+        (
+            handlers.default.as_ref().map(|p| p.id()),
+            handlers.others.iter().map(|p| p.id()).collect(),
+        )
+    }
+
+    #[test]
+    fn handler_precedence() {
+        let db = database();
+        let programs = [
+            program("archiver.desktop", &["application/gzip"]),
+            program("tar.desktop", &["application/x-compressed-tar"]),
+            program("extra.desktop", &[]),
+            program("unwanted.desktop", &["application/gzip"]),
+        ];
+        let lists = [
+            MimeApps::parse(
+                "[Default Applications]\n\
+                 application/gzip=missing.desktop;archiver.desktop\n\
+                 [Removed Associations]\n\
+                 application/gzip=extra.desktop;unwanted.desktop\n",
+            ),
+            MimeApps::parse(
+                "[Default Applications]\n\
+                 application/gzip=tar.desktop\n\
+                 [Added Associations]\n\
+                 application/gzip=extra.desktop\n",
+            ),
+        ];
+
+        // the first installed default of the first list, removed associations don't come back
+        // through later lists or the programs' own types
+        let handlers = find_handlers(&lists, db.ancestors("application/x-gzip"), &programs);
+        assert_eq!(ids(&handlers), (Some("archiver.desktop"), vec![]));
+
+        // the parent type's default, the program for the type itself still shows up
+        let handlers = find_handlers(
+            &lists,
+            db.ancestors("application/x-compressed-tar"),
+            &programs,
+        );
+        assert_eq!(
+            ids(&handlers),
+            (Some("archiver.desktop"), vec!["tar.desktop"])
+        );
+
+        // removals only apply to the lists after them
+        let lists = [lists[1].clone(), lists[0].clone()];
+        let handlers = find_handlers(&lists, db.ancestors("application/gzip"), &programs);
+        assert_eq!(
+            ids(&handlers),
+            (
+                Some("tar.desktop"),
+                vec!["extra.desktop", "archiver.desktop"]
+            )
+        );
+
+        // without defaults the most specific association is picked
+        let handlers = find_handlers(&[], db.ancestors("application/x-compressed-tar"), &programs);
+        assert_eq!(
+            ids(&handlers),
+            (
+                Some("tar.desktop"),
+                vec!["archiver.desktop", "unwanted.desktop"]
+            )
+        );
+    }
+}
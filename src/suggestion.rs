@@ -1,33 +1,278 @@
+use iced::futures::channel::oneshot;
+use iced::widget::{image, svg};
 use iced::Element;
-use std::rc::Rc;
-use std::collections::VecDeque;
+use std::any::Any;
 use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use crate::icons::IconTheme;
 use crate::ui::LanchMessage;
 
+// ordered from the best to the worst match, so sorting by level ranks the suggestions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum MatchLevel {
     Exact,
+    // the query is made of the initials of the words in the name
+    Initials,
     Contained,
+    // only matches with the given number of typos, ranked below everything else
+    Typo(u8),
+    // only shown when no module found anything else
+    Fallback,
     NoMatch
 }
 
 /// Generic trait for things that are displayed in the suggestion list
-pub trait Suggestion: Display + Debug {
+pub trait Suggestion: Display + Debug + Send + Sync {
     // display the element as a collection of iced widgets
-    fn view(&self) -> Element<LanchMessage>;
+    fn view(&self) -> Element<'_, LanchMessage>;
 
     // triggered when the user presses enter on the selected item
     fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>>;
 
     // condition checked to decide whether or not to display the suggestion based on the query
     fn matches(&self, query: &str) -> MatchLevel;
+
+    // shows something that changes by the second, like a clock. The list is redrawn every
+    // second while such a suggestion is visible.
+    fn live(&self) -> bool {
+        false
+    }
+}
+
+// size (in px) icons are displayed at in the suggestion list
+const ICON_SIZE: u16 = 20;
+
+/// Icon widget for an image file, if it is in a supported format
+pub fn view_icon<'a>(path: &Path) -> Option<Element<'a, LanchMessage>> {
+    match path.extension()?.to_str()? {
+        "png" | "jpg" => Some(image(path).width(ICON_SIZE).height(ICON_SIZE).into()),
+        "svg" => Some(
+            svg::Svg::from_path(path)
+                .width(ICON_SIZE)
+                .height(ICON_SIZE)
+                .into(),
+        ),
+        _ => None,
+    }
+}
+
+/// A suggestion paired with how well it matched the query
+pub type Match = (MatchLevel, Arc<dyn Suggestion>);
+
+/// Set once the query a module is working on is outdated. Modules doing a lot of work should
+/// check it every now and then and bail out early.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Handed to modules on initialization and cache refreshes
+pub struct ModuleContext<'a> {
+    // the module's section of the config, without the options lanch handles itself
+    pub settings: &'a toml::Table,
+
+    pub icon_theme: &'a IconTheme,
+
+    // where the module can keep state across runs, not created up front
+    pub data_dir: PathBuf,
 }
 
 /// Suggestion modules add matching modules to the suggestion list based on the passed query
-pub trait SuggestionModule {
-    fn get_matches(&mut self, query: &str, v: &mut VecDeque<Rc<dyn Suggestion>>);
+pub trait SuggestionModule: Send + Sync {
+    // identifies the module in the config and is shown in the info bar while it is active
+    fn name(&self) -> &str;
+
+    // one line summary shown in the module list
+    fn description(&self) -> &str;
+
+    // called once before the module is queried for the first time
+    fn init(&mut self, _ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    // the user asked for cached data to be regenerated
+    fn refresh(&self, _ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    // lanch is about to exit, state that should survive goes to `ModuleContext::data_dir`
+    fn shutdown(&self) {}
+
+    // prefix ("!") or keyword ("time") that sends a query to this module alone. The module only
+    // gets to see the rest of the query.
+    fn trigger(&self) -> Option<&str> {
+        None
+    }
+
+    // whether the module is asked for matches when no trigger is used
+    fn global(&self) -> bool {
+        true
+    }
+
+    // errors end up in the info bar, matches added before the error are still shown
+    fn get_matches(
+        &self,
+        query: &str,
+        cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+// a query waiting for a module's worker, the matches and error go back through `reply`
+struct Job {
+    query: String,
+    cancel: CancelToken,
+    reply: oneshot::Sender<(Vec<Match>, Option<String>)>,
+}
+
+// what a module panicked with, panics usually carry a string
+fn panic_message(panic: &(dyn Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(msg) => msg,
+        None => panic
+            .downcast_ref::<String>()
+            .map_or("unknown error", String::as_str),
+    }
+}
+
+/// A thread a module answers its queries on, so slow modules don't block the ui. Queries are
+/// worked through in order, outdated ones are skipped once their token is cancelled.
+pub struct ModuleWorker {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl ModuleWorker {
+    pub fn new(module: Arc<dyn SuggestionModule>) -> Self {
+        let (jobs, rx) = mpsc::channel::<Job>();
+
+        // ends once the worker is dropped along with the sending side
+        thread::spawn(move || {
+            for job in rx {
+                let mut matches = Vec::new();
+                let mut error = None;
+                if !job.cancel.is_cancelled() {
+                    // a panicking module would take the worker down and go quiet for the rest
+                    // of the session, this way it only loses the one query
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        module.get_matches(&job.query, &job.cancel, &mut matches)
+                    }));
+                    error = match result {
+                        Ok(result) => result.err().map(|e| e.to_string()),
+                        Err(panic) => {
+                            matches.clear();
+                            Some(format!("crashed ({})", panic_message(&*panic)))
+                        }
+                    }
+                    .map(|e| format!("{}: {}", module.name(), e));
+                }
+                // the receiver is gone if the query got replaced in the meantime
+                let _ = job.reply.send((matches, error));
+            }
+        });
+
+        Self { jobs }
+    }
+
+    /// The matches for `query` along with the error the module ran into, if any
+    pub fn query(
+        &self,
+        query: String,
+        cancel: CancelToken,
+    ) -> impl Future<Output = (Vec<Match>, Option<String>)> {
+        let (reply, rx) = oneshot::channel();
+        let _ = self.jobs.send(Job {
+            query,
+            cancel,
+            reply,
+        });
+
+        async move { rx.await.unwrap_or_default() }
+    }
 }
 
+pub mod matching;
+pub mod plugin;
+pub mod registry;
+pub mod script;
+pub mod scripting;
 pub mod executable;
 pub mod timedate;
+pub mod websearch;
 pub mod command;
+pub mod open;
+pub mod custom;
 pub mod builtin;
+pub mod calculator;
+pub mod units;
+pub mod currency;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::futures::executor::block_on;
+
+    // panics on "boom", answers everything else
+    struct Fragile;
+
+    impl SuggestionModule for Fragile {
+        fn name(&self) -> &str {
+            "fragile"
+        }
+
+        fn description(&self) -> &str {
+            ""
+        }
+
+        fn get_matches(
+            &self,
+            query: &str,
+            _cancel: &CancelToken,
+            v: &mut Vec<Match>,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            if query == "boom" {
+                panic!("boom");
+            }
+            v.push((
+                MatchLevel::Exact,
+                Arc::new(executable::ExecutableSuggestion::new(query, query)),
+            ));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn workers_survive_panicking_modules() {
+        let worker = ModuleWorker::new(Arc::new(Fragile));
+
+        let (matches, error) = block_on(worker.query(String::from("boom"), CancelToken::default()));
+        assert!(matches.is_empty());
+        assert_eq!(error.as_deref(), Some("fragile: crashed (boom)"));
+
+        let (matches, error) = block_on(worker.query(String::from("ls"), CancelToken::default()));
+        assert_eq!(matches.len(), 1);
+        assert_eq!(error, None);
+    }
+
+    #[test]
+    fn workers_skip_cancelled_queries() {
+        let worker = ModuleWorker::new(Arc::new(Fragile));
+        let cancel = CancelToken::default();
+        cancel.cancel();
+        let (matches, error) = block_on(worker.query(String::from("boom"), cancel));
+        assert!(matches.is_empty());
+        assert_eq!(error, None);
+    }
+}
@@ -1,6 +1,8 @@
 use iced::widget::{column, text};
 use std::fmt::Display;
+use std::sync::Arc;
 
+use super::matching::normalize;
 use super::*;
 
 #[derive(Debug)]
@@ -10,7 +12,7 @@ pub struct BuiltInSuggestion {
 }
 
 impl Suggestion for BuiltInSuggestion {
-    fn view(&self) -> Element<LanchMessage> {
+    fn view(&self) -> Element<'_, LanchMessage> {
         column![text(&self.name),].into()
     }
 
@@ -19,15 +21,15 @@ impl Suggestion for BuiltInSuggestion {
     }
 
     fn matches(&self, query: &str) -> MatchLevel {
-        if let Some(cmd) = query.strip_prefix('/') {
-            if self.name == cmd {
-                return MatchLevel::Exact;
-            } else {
-                return MatchLevel::Contained;
-            }
-        }
+        let cmd = normalize(query);
 
-        MatchLevel::NoMatch
+        if self.name == cmd {
+            MatchLevel::Exact
+        } else if self.name.contains(&cmd) {
+            MatchLevel::Contained
+        } else {
+            MatchLevel::NoMatch
+        }
     }
 }
 
@@ -38,22 +40,26 @@ impl Display for BuiltInSuggestion {
 }
 
 pub struct BuiltInModule {
-    cmds: Vec<Rc<BuiltInSuggestion>>,
+    cmds: Vec<Arc<BuiltInSuggestion>>,
 }
 
 impl BuiltInModule {
     pub fn new() -> Self {
         Self {
             cmds: vec![
-                Rc::new(BuiltInSuggestion {
+                Arc::new(BuiltInSuggestion {
                     name: String::from("refresh cache"),
                     execute_fn: || Ok(Some(LanchMessage::RefreshCache)),
                 }),
-                Rc::new(BuiltInSuggestion {
+                Arc::new(BuiltInSuggestion {
+                    name: String::from("modules"),
+                    execute_fn: || Ok(Some(LanchMessage::SwitchLayout(crate::ui::Layout::Modules))),
+                }),
+                Arc::new(BuiltInSuggestion {
                     name: String::from("license"),
                     execute_fn: || Ok(Some(LanchMessage::SwitchLayout(crate::ui::Layout::License))),
                 }),
-                Rc::new(BuiltInSuggestion {
+                Arc::new(BuiltInSuggestion {
                     name: String::from("help"),
                     // execute_fn: || Ok(None),
                     execute_fn: || todo!(),
@@ -64,13 +70,35 @@ impl BuiltInModule {
 }
 
 impl SuggestionModule for BuiltInModule {
-    fn get_matches(&mut self, query: &str, v: &mut VecDeque<Rc<dyn Suggestion>>) {
+    fn name(&self) -> &str {
+        "builtin"
+    }
+
+    fn description(&self) -> &str {
+        "Commands controlling lanch itself"
+    }
+
+    fn trigger(&self) -> Option<&str> {
+        Some("/")
+    }
+
+    fn global(&self) -> bool {
+        false
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         for cmd in &self.cmds {
             match cmd.matches(query) {
-                MatchLevel::Exact => v.push_front(Rc::clone(cmd) as Rc<dyn Suggestion>),
-                MatchLevel::Contained => v.push_back(Rc::clone(cmd) as Rc<dyn Suggestion>),
                 MatchLevel::NoMatch => {}
+                level => v.push((level, Arc::clone(cmd) as Arc<dyn Suggestion>)),
             }
         }
+
+        Ok(())
     }
 }
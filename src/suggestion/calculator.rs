@@ -0,0 +1,546 @@
+use iced::widget::{column, horizontal_space, row, text, vertical_space};
+use iced::{theme, Color, Length};
+use rust_decimal::prelude::*;
+use rust_decimal::MathematicalOps;
+
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::Chars;
+use std::sync::Arc;
+
+use super::*;
+
+// digits after the decimal point shown, the approximated functions are off further down
+const DISPLAY_PRECISION: u32 = 16;
+
+// results closer to zero than this are shown in full rather than rounded away
+const TINY: Decimal = Decimal::from_parts(1, 0, 0, false, 10);
+
+// how deep parentheses, signs and functions may nest, deeper expressions would overflow the
+// stack of the module's thread
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    // the flag is set for hex, octal and binary literals
+    Number(Decimal, bool),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+}
+
+fn tokenize(expr: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '0'..='9' | '.' => tokens.push(number(&mut chars)?),
+            c if c.is_alphabetic() && c != 'π' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric()) {
+                    ident.extend(c.to_lowercase());
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    'π' => Token::Ident(String::from("pi")),
+                    '√' => Token::Ident(String::from("sqrt")),
+                    '×' | '·' => Token::Op('*'),
+                    '÷' => Token::Op('/'),
+                    '−' => Token::Op('-'),
+                    // "**" is a power as well
+                    '*' if chars.peek() == Some(&'*') => {
+                        chars.next();
+                        Token::Op('^')
+                    }
+                    '+' | '-' | '*' | '/' | '^' | '%' | '!' => Token::Op(c),
+                    _ => return None,
+                });
+            }
+        }
+    }
+
+    Some(tokens)
+}
+
+fn number(chars: &mut Peekable<Chars>) -> Option<Token> {
+    let mut ahead = chars.clone();
+    let radix = match (ahead.next(), ahead.next()) {
+        (Some('0'), Some('x' | 'X')) => Some(16),
+        (Some('0'), Some('o' | 'O')) => Some(8),
+        (Some('0'), Some('b' | 'B')) => Some(2),
+        _ => None,
+    };
+
+    if let Some(radix) = radix {
+        chars.nth(1);
+        let mut digits = String::new();
+        while let Some(&c) = chars
+            .peek()
+            .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+        {
+            if c != '_' {
+                digits.push(c);
+            }
+            chars.next();
+        }
+        let value = i128::from_str_radix(&digits, radix).ok()?;
+        return Some(Token::Number(Decimal::from_i128(value)?, true));
+    }
+
+    let mut literal = String::new();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '0'..='9' | '.' => literal.push(c),
+            '_' => {}
+            // scientific notation, as long as digits follow ("2e" is 2 times e)
+            'e' | 'E' => {
+                let mut ahead = chars.clone();
+                ahead.next();
+                let sign = ahead.next_if(|c| *c == '-' || *c == '+');
+                if !ahead.peek().is_some_and(|c| c.is_ascii_digit()) {
+                    break;
+                }
+
+                literal.push('e');
+                literal.extend(sign);
+                chars.nth(sign.is_some() as usize);
+                continue;
+            }
+            _ => break,
+        }
+        chars.next();
+    }
+
+    let value = if literal.contains('e') {
+        Decimal::from_scientific(&literal).ok()?
+    } else {
+        Decimal::from_str(&literal).ok()?
+    };
+    Some(Token::Number(value, false))
+}
+
+/// Evaluates the tokens of an expression by recursive descent
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+
+    // how many nested rules are being parsed, see `MAX_DEPTH`
+    depth: usize,
+
+    // whether a hex, octal or binary literal was used
+    radix_literal: bool,
+}
+
+// a value along with whether it was written as a percentage
+type Value = (Decimal, bool);
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    // every recursion goes through here, so the depth is capped however the rules nest
+    fn nested<T>(&mut self, rule: impl FnOnce(&mut Self) -> Option<T>) -> Option<T> {
+        if self.depth >= MAX_DEPTH {
+            return None;
+        }
+        self.depth += 1;
+        let value = rule(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Option<Decimal> {
+        let mut value = self.term()?.0;
+
+        loop {
+            let sign = if self.eat('+') {
+                Decimal::ONE
+            } else if self.eat('-') {
+                Decimal::NEGATIVE_ONE
+            } else {
+                return Some(value);
+            };
+
+            // "200 + 10%" adds 10 percent of 200
+            let (rhs, percent) = self.term()?;
+            let rhs = if percent {
+                value.checked_mul(rhs)?
+            } else {
+                rhs
+            };
+            value = value.checked_add(sign * rhs)?;
+        }
+    }
+
+    // term := unary (('*' | '/' | 'mod' | nothing) unary)*
+    fn term(&mut self) -> Option<Value> {
+        let mut value = self.unary()?;
+
+        loop {
+            let (lhs, _) = value;
+            value = if self.eat('*') {
+                (lhs.checked_mul(self.unary()?.0)?, false)
+            } else if self.eat('/') {
+                (lhs.checked_div(self.unary()?.0)?, false)
+            } else if self.peek() == Some(&Token::Ident(String::from("mod"))) {
+                self.pos += 1;
+                (lhs.checked_rem(self.unary()?.0)?, false)
+            } else if matches!(
+                self.peek(),
+                Some(Token::Number(..) | Token::Ident(_) | Token::LParen)
+            ) {
+                // "2pi" or "3(1 + 2)"
+                (lhs.checked_mul(self.unary()?.0)?, false)
+            } else {
+                return Some(value);
+            };
+        }
+    }
+
+    // unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Option<Value> {
+        if self.eat('-') {
+            let (value, percent) = self.nested(Self::unary)?;
+            return Some((-value, percent));
+        }
+        if self.eat('+') {
+            return self.nested(Self::unary);
+        }
+        self.power()
+    }
+
+    // power := postfix ('^' unary)?, so powers are right associative and bind tighter than
+    // a leading minus
+    fn power(&mut self) -> Option<Value> {
+        let (base, percent) = self.postfix()?;
+        if !self.eat('^') {
+            return Some((base, percent));
+        }
+
+        let (exp, _) = self.nested(Self::unary)?;
+        let value = match exp.to_i64().filter(|_| exp.fract().is_zero()) {
+            Some(exp) => base.checked_powi(exp)?,
+            None => via_f64(base, |b| b.powf(exp.to_f64().unwrap_or(f64::NAN)))?,
+        };
+        Some((value, false))
+    }
+
+    // postfix := primary ('%' | '!')*
+    fn postfix(&mut self) -> Option<Value> {
+        let mut value = (self.primary()?, false);
+
+        loop {
+            if self.eat('%') {
+                value = (value.0.checked_div(Decimal::ONE_HUNDRED)?, true);
+            } else if self.eat('!') {
+                value = (factorial(value.0)?, false);
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    // primary := number | constant | function primary | '(' expr ')'
+    fn primary(&mut self) -> Option<Decimal> {
+        match self.next()? {
+            Token::Number(value, radix_literal) => {
+                self.radix_literal |= radix_literal;
+                Some(value)
+            }
+            Token::LParen => {
+                let value = self.nested(Self::expr)?;
+                (self.next()? == Token::RParen).then_some(value)
+            }
+            Token::Ident(name) => match name.as_str() {
+                "pi" => Some(Decimal::PI),
+                "tau" => Some(Decimal::TWO_PI),
+                "e" => Some(Decimal::E),
+                _ => {
+                    // "sqrt 16" works as well as "sqrt(16)"
+                    let (arg, _) = self.nested(Self::power)?;
+                    function(&name, arg)
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+fn function(name: &str, x: Decimal) -> Option<Decimal> {
+    match name {
+        "sqrt" => x.sqrt(),
+        "sin" => trig(x, f64::sin),
+        "cos" => trig(x, f64::cos),
+        "tan" => trig(x, f64::tan),
+        "ln" => via_f64(x, f64::ln),
+        "log" | "lg" => via_f64(x, f64::log10),
+        "log2" => via_f64(x, f64::log2),
+        "exp" => via_f64(x, f64::exp),
+        "abs" => Some(x.abs()),
+        "round" => Some(x.round()),
+        "floor" => Some(x.floor()),
+        "ceil" => Some(x.ceil()),
+        _ => None,
+    }
+}
+
+// the series rust_decimal uses for these are further off than f64, so they go through f64 and
+// keep the digits it gets right
+fn via_f64(x: Decimal, f: impl Fn(f64) -> f64) -> Option<Decimal> {
+    let result = f(x.to_f64()?);
+    if !result.is_finite() {
+        return None;
+    }
+    Decimal::from_f64(result)?.round_sf(15)
+}
+
+// pi isn't exact, so sin(pi) lands near zero instead of on it
+fn trig(x: Decimal, f: impl Fn(f64) -> f64) -> Option<Decimal> {
+    let result = via_f64(x, f)?;
+    if result.abs() < Decimal::new(1, 14) {
+        return Some(Decimal::ZERO);
+    }
+    Some(result)
+}
+
+fn factorial(n: Decimal) -> Option<Decimal> {
+    if !n.fract().is_zero() || n.is_sign_negative() {
+        return None;
+    }
+    (1..=n.to_u64()?).try_fold(Decimal::ONE, |acc, i| acc.checked_mul(Decimal::from(i)))
+}
+
+/// The result of an expression, as long as it is more than a plain number
+fn evaluate(expr: &str) -> Option<(Decimal, bool)> {
+    let tokens = tokenize(expr)?;
+
+    // "42" or "pi" alone isn't worth a suggestion, "0x2a" is
+    let is_calculation = tokens.iter().any(|t| !matches!(t, Token::Number(_, false)))
+        && !matches!(tokens.as_slice(), [Token::Ident(_)]);
+    if !is_calculation {
+        return None;
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        depth: 0,
+        radix_literal: false,
+    };
+    let value = parser.expr()?;
+
+    // everything has to be used up, otherwise it wasn't an expression after all
+    if parser.pos != parser.tokens.len() {
+        return None;
+    }
+    Some((value, parser.radix_literal))
+}
+
+fn format_result(value: Decimal) -> String {
+    let value = if value.abs() < TINY {
+        value
+    } else {
+        value.round_dp(DISPLAY_PRECISION)
+    };
+    value.normalize().to_string()
+}
+
+/// The result of a calculation
+#[derive(Debug)]
+pub struct CalculatorSuggestion {
+    expr: String,
+    result: String,
+
+    // the result in hex, for calculations with hex, octal or binary literals
+    hex: Option<String>,
+}
+
+impl Suggestion for CalculatorSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let mut result = row![text(format!("= {}", self.result)).size(24)];
+        if let Some(hex) = &self.hex {
+            result = result.push(horizontal_space(Length::Fixed(8f32))).push(
+                text(hex)
+                    .size(24)
+                    .style(theme::Text::Color(Color::from([0.6, 0.6, 0.6]))),
+            );
+        }
+
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                column![
+                    text(&self.expr).style(theme::Text::Color(Color::from([0.6, 0.6, 0.6]))),
+                    result,
+                ],
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        Ok(Some(LanchMessage::CopyToClipboard(self.result.clone())))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for CalculatorSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Calculator")
+    }
+}
+
+pub struct CalculatorModule;
+
+impl SuggestionModule for CalculatorModule {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn description(&self) -> &str {
+        "Evaluates math expressions, Enter copies the result"
+    }
+
+    fn trigger(&self) -> Option<&str> {
+        Some("=")
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (value, radix_literal) = match evaluate(query) {
+            Some(result) => result,
+            None => return Ok(()),
+        };
+
+        let hex = match value.to_i128() {
+            Some(int) if radix_literal && value.fract().is_zero() => Some(format!(
+                "{}0x{:x}",
+                if int < 0 { "-" } else { "" },
+                int.abs()
+            )),
+            _ => None,
+        };
+
+        v.push((
+            MatchLevel::Exact,
+            Arc::new(CalculatorSuggestion {
+                expr: query.trim().to_string(),
+                result: format_result(value),
+                hex,
+            }),
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(expr: &str) -> Option<String> {
+        evaluate(expr).map(|(value, _)| format_result(value))
+    }
+
+    #[test]
+    fn decimal_arithmetic_is_exact() {
+        assert_eq!(calc("0.1 + 0.2").as_deref(), Some("0.3"));
+        assert_eq!(calc("1 / 3 * 3").as_deref(), Some("1"));
+        assert_eq!(calc("2 + 3 * 4").as_deref(), Some("14"));
+        assert_eq!(calc("(2 + 3) * 4").as_deref(), Some("20"));
+        assert_eq!(calc("-3 - -2").as_deref(), Some("-1"));
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        assert_eq!(
+            calc(&format!("{}1 + 1{}", "(".repeat(50), ")".repeat(50))).as_deref(),
+            Some("2")
+        );
+
+        // used to overflow the stack and abort lanch
+        let deep = format!("{}1{}", "(".repeat(20_000), ")".repeat(20_000));
+        assert_eq!(calc(&deep), None);
+        assert_eq!(calc(&format!("{}1", "-".repeat(20_000))), None);
+        assert_eq!(calc(&format!("2{}", "^2".repeat(20_000))), None);
+        assert_eq!(calc(&format!("{}16", "sqrt ".repeat(20_000))), None);
+    }
+
+    #[test]
+    fn operators_and_constants() {
+        assert_eq!(calc("2^10").as_deref(), Some("1024"));
+        assert_eq!(calc("2^-1").as_deref(), Some("0.5"));
+        assert_eq!(calc("5!").as_deref(), Some("120"));
+        assert_eq!(calc("10 mod 4").as_deref(), Some("2"));
+        assert_eq!(calc("50%").as_deref(), Some("0.5"));
+        assert!(calc("2 * pi").unwrap().starts_with("6.28318530717958"));
+    }
+
+    #[test]
+    fn functions() {
+        assert_eq!(calc("sqrt(16)").as_deref(), Some("4"));
+        assert_eq!(calc("sin(0)").as_deref(), Some("0"));
+        assert_eq!(calc("sin(pi)").as_deref(), Some("0"));
+        assert_eq!(calc("ln(e)").as_deref(), Some("1"));
+        assert!(calc("2^0.5").unwrap().starts_with("1.41421356237"));
+    }
+
+    #[test]
+    fn radix_literals() {
+        assert_eq!(
+            evaluate("0x2a").map(|(v, radix)| (format_result(v), radix)),
+            Some((String::from("42"), true))
+        );
+        assert_eq!(calc("0b101 + 0o7").as_deref(), Some("12"));
+        assert_eq!(evaluate("1 + 1").map(|(_, radix)| radix), Some(false));
+    }
+
+    #[test]
+    fn not_calculations() {
+        // plain numbers and words aren't worth a suggestion
+        assert_eq!(calc("42"), None);
+        assert_eq!(calc("pi"), None);
+        assert_eq!(calc("firefox"), None);
+        assert_eq!(calc("1 +"), None);
+        assert_eq!(calc("1 / 0"), None);
+        assert_eq!(calc("(-1)!"), None);
+        assert_eq!(calc("sqrt(-1)"), None);
+    }
+}
@@ -4,38 +4,51 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
-use std::process::Command;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
+use crate::launcher;
+
+use super::matching::normalize;
 use super::*;
 
+// number of commands remembered across runs
+const HISTORY_LEN: usize = 100;
+
+// previously run commands, most recent last
+type History = Arc<Mutex<Vec<String>>>;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CommandSuggestion {
     cmd: String,
+
+    // the command gets recorded here once it ran
+    #[serde(skip)]
+    history: History,
 }
 
 impl CommandSuggestion {
-    pub fn with_cmd(cmd: &str) -> Self {
+    pub fn with_cmd(cmd: &str, history: &History) -> Self {
         CommandSuggestion {
             cmd: String::from(cmd),
+            history: Arc::clone(history),
         }
     }
 }
 
 impl Suggestion for CommandSuggestion {
-    fn view(&self) -> Element<LanchMessage> {
+    fn view(&self) -> Element<'_, LanchMessage> {
         text(format!("Command: \"{}\"", self.cmd)).into()
     }
 
     fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
-        let mut exec = self.cmd.split_whitespace();
-        let mut cmd = Command::new(exec.next().unwrap());
-        exec.for_each(|arg| {
-            cmd.arg(arg);
-        });
-        match cmd.spawn() {
-            Ok(_) => Ok(None),
-            Err(e) => Err(Box::new(e)),
-        }
+        launcher::run(&self.cmd, false)?;
+
+        let mut history = self.history.lock().unwrap();
+        history.retain(|c| *c != self.cmd);
+        history.push(self.cmd.clone());
+        Ok(None)
     }
 
     fn matches(&self, _query: &str) -> MatchLevel {
@@ -49,12 +62,76 @@ impl Display for CommandSuggestion {
     }
 }
 
-pub struct CommandModule;
+#[derive(Default)]
+pub struct CommandModule {
+    history: History,
+    history_file: PathBuf,
+}
 
 impl SuggestionModule for CommandModule {
-    fn get_matches(&mut self, query: &str, v: &mut VecDeque<Rc<dyn Suggestion>>) {
-        if query.starts_with('!') && query != "!" {
-            v.push_front(Rc::new(CommandSuggestion::with_cmd(query.strip_prefix('!').unwrap())))
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn description(&self) -> &str {
+        "Runs the query as a command, remembers past ones"
+    }
+
+    fn trigger(&self) -> Option<&str> {
+        Some("!")
+    }
+
+    fn global(&self) -> bool {
+        false
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        self.history_file = ctx.data_dir.join("history");
+
+        // no history yet on the first run
+        if let Ok(data) = fs::read_to_string(&self.history_file) {
+            *self.history.lock().unwrap() = data.lines().map(String::from).collect();
+        }
+        Ok(())
+    }
+
+    fn shutdown(&self) {
+        let history = self.history.lock().unwrap();
+        let start = history.len().saturating_sub(HISTORY_LEN);
+
+        let written = self
+            .history_file
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&self.history_file, history[start..].join("\n")));
+        if let Err(e) = written {
+            eprintln!("[COMMAND] failed to save history: {}", e);
         }
     }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if !query.is_empty() {
+            v.push((
+                MatchLevel::Exact,
+                Arc::new(CommandSuggestion::with_cmd(query, &self.history)),
+            ));
+        }
+
+        let query_norm = normalize(query);
+        for past in self.history.lock().unwrap().iter().rev() {
+            if past != query && normalize(past).contains(&query_norm) {
+                v.push((
+                    MatchLevel::Contained,
+                    Arc::new(CommandSuggestion::with_cmd(past, &self.history)),
+                ));
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,519 @@
+use chrono::{Local, NaiveDate};
+use iced::widget::{column, horizontal_space, row, text, vertical_space};
+use iced::{theme, Color, Length};
+use rust_decimal::prelude::*;
+use serde::Deserialize;
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+
+use crate::config::{data_home, LanchConfig};
+
+use super::units::{split_number, SEPARATORS};
+use super::*;
+
+// the daily reference rates of the European Central Bank
+const DEFAULT_URL: &str = "https://www.ecb.europa.eu/stats/eurofxref/eurofxref-daily.xml";
+
+// the ECB doesn't publish on weekends and holidays, so a few days are normal
+const DEFAULT_MAX_AGE: i64 = 7;
+
+// currencies the ECB publishes rates for, used to tell "100 usd to eur" apart from other
+// queries while no rates are available
+const KNOWN_CURRENCIES: [&str; 31] = [
+    "EUR", "USD", "JPY", "BGN", "CZK", "DKK", "GBP", "HUF", "PLN", "RON", "SEK", "CHF", "ISK",
+    "NOK", "TRY", "AUD", "BRL", "CAD", "CNY", "HKD", "IDR", "ILS", "INR", "KRW", "MXN", "MYR",
+    "NZD", "PHP", "SGD", "THB", "ZAR",
+];
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CurrencyConfig {
+    // the ECB XML file or a JSON file with `base`, `date` and `rates`, defaults to rates.xml in
+    // the module's data dir
+    rates_file: Option<PathBuf>,
+
+    // where `lanch --refresh-rates` downloads the rates file from
+    rates_url: Option<String>,
+
+    // rates older than this many days get a warning
+    max_age_days: Option<i64>,
+}
+
+impl CurrencyConfig {
+    fn from_settings(settings: &toml::Table) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(toml::Value::Table(settings.clone()).try_into()?)
+    }
+
+    fn rates_file(&self, data_dir: &Path) -> PathBuf {
+        self.rates_file
+            .clone()
+            .unwrap_or_else(|| data_dir.join("rates.xml"))
+    }
+}
+
+/// `{"base": "EUR", "date": "2024-05-03", "rates": {"USD": 1.0765}}`, the layout most rate
+/// APIs use
+#[derive(Debug, Deserialize)]
+struct JsonRates {
+    #[serde(default = "default_base")]
+    base: String,
+    date: String,
+    rates: HashMap<String, serde_json::Number>,
+}
+
+fn default_base() -> String {
+    String::from("EUR")
+}
+
+#[derive(Debug)]
+struct Rates {
+    date: NaiveDate,
+
+    // units of each currency one unit of the base currency buys, the base itself included
+    rates: HashMap<String, Decimal>,
+}
+
+impl Rates {
+    fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let data = fs::read_to_string(path)?;
+
+        let (base, date, rates) = if data.trim_start().starts_with('{') {
+            let json: JsonRates = serde_json::from_str(&data)?;
+            let rates = json
+                .rates
+                .into_iter()
+                .map(|(code, rate)| Ok((code, Decimal::from_str(&rate.to_string())?)))
+                .collect::<Result<_, rust_decimal::Error>>()?;
+            (json.base, json.date, rates)
+        } else {
+            let (date, rates) = parse_ecb_xml(&data)?;
+            (String::from("EUR"), date, rates)
+        };
+
+        let mut rates: HashMap<String, Decimal> = rates;
+        rates.insert(base.to_uppercase(), Decimal::ONE);
+        Ok(Self {
+            date: NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .map_err(|e| format!("bad date {:?}: {}", date, e))?,
+            rates: rates
+                .into_iter()
+                .map(|(code, rate)| (code.to_uppercase(), rate))
+                .collect(),
+        })
+    }
+
+    fn convert(&self, amount: Decimal, from: &str, to: &str) -> Option<(Decimal, Decimal)> {
+        let from = self.rates.get(from)?;
+        let to = self.rates.get(to)?;
+        let rate = to.checked_div(*from)?;
+        Some((amount.checked_mul(rate)?, rate))
+    }
+}
+
+// the attributes of a tag, `<Cube currency='USD' rate='1.0765'/>` has currency and rate
+fn attributes(tag: &str) -> HashMap<&str, &str> {
+    let mut attrs = HashMap::new();
+    let mut rest = tag;
+
+    while let Some((name, value)) = rest.split_once('=') {
+        let name = name.split_whitespace().last().unwrap_or_default();
+        let value = value.trim_start();
+        let quote = match value.chars().next() {
+            Some(q @ ('"' | '\'')) => q,
+            _ => break,
+        };
+        let (value, after) = match value[1..].split_once(quote) {
+            Some(split) => split,
+            None => break,
+        };
+        attrs.insert(name, value);
+        rest = after;
+    }
+    attrs
+}
+
+/// The `<Cube>` elements of the ECB reference rates, one holding the date and one per currency
+fn parse_ecb_xml(
+    data: &str,
+) -> Result<(String, HashMap<String, Decimal>), Box<dyn std::error::Error>> {
+    let mut date = None;
+    let mut rates = HashMap::new();
+
+    for element in data.split("<Cube").skip(1) {
+        let tag = element.split('>').next().unwrap_or_default();
+        let attrs = attributes(tag);
+
+        if let Some(time) = attrs.get("time") {
+            date = Some(time.to_string());
+        }
+        if let (Some(currency), Some(rate)) = (attrs.get("currency"), attrs.get("rate")) {
+            rates.insert(currency.to_string(), Decimal::from_str(rate)?);
+        }
+    }
+
+    match date {
+        Some(date) if !rates.is_empty() => Ok((date, rates)),
+        _ => Err("not an ECB reference rates file".into()),
+    }
+}
+
+/// Downloads the rates file to where the currency module reads it from, for
+/// `lanch --refresh-rates`
+pub fn refresh_rates(config: &LanchConfig) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let settings = config
+        .modules
+        .get("currency")
+        .map(|m| m.settings.clone())
+        .unwrap_or_default();
+    let config = CurrencyConfig::from_settings(&settings)?;
+    let path = config.rates_file(&data_home().join("lanch").join("currency"));
+    let url = config.rates_url.as_deref().unwrap_or(DEFAULT_URL);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // lanch itself stays off the network, curl or wget do the downloading
+    let partial = path.with_extension("part");
+    let partial_arg = partial.to_string_lossy().into_owned();
+    let downloaders = [
+        (
+            "curl",
+            vec!["-fsSL", "--max-time", "60", "-o", &partial_arg, url],
+        ),
+        ("wget", vec!["-q", "-T", "60", "-O", &partial_arg, url]),
+    ];
+
+    let mut last_error = String::from("neither curl nor wget are installed");
+    for (program, args) in downloaders {
+        let status = match Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .status()
+        {
+            Ok(status) => status,
+            Err(_) => continue,
+        };
+        if !status.success() {
+            last_error = format!("{} failed to download {} ({})", program, url, status);
+            continue;
+        }
+
+        // a broken download shouldn't replace rates that still work
+        if let Err(e) = Rates::load(&partial) {
+            let _ = fs::remove_file(&partial);
+            return Err(format!("{} doesn't hold exchange rates: {}", url, e).into());
+        }
+        fs::rename(&partial, &path)?;
+        return Ok(path);
+    }
+
+    let _ = fs::remove_file(&partial);
+    Err(last_error.into())
+}
+
+// "$", "€" and friends for the currencies that have an unambiguous one
+fn currency_code(word: &str) -> Option<String> {
+    let code = match word {
+        "$" => "USD",
+        "€" => "EUR",
+        "£" => "GBP",
+        "¥" => "JPY",
+        word if word.len() == 3 && word.chars().all(|c| c.is_ascii_alphabetic()) => word,
+        _ => return None,
+    };
+    Some(code.to_uppercase())
+}
+
+/// Parses "<amount> <currency> to <currency>"
+fn parse(query: &str) -> Option<(Decimal, String, String)> {
+    let (amount, rest) = split_number(query.trim())?;
+    let words: Vec<&str> = rest.split_whitespace().collect();
+
+    match words.as_slice() {
+        [from, separator, to] if SEPARATORS.contains(&separator.to_lowercase().as_str()) => {
+            Some((amount, currency_code(from)?, currency_code(to)?))
+        }
+        _ => None,
+    }
+}
+
+fn format_amount(amount: Decimal) -> String {
+    let rounded = if amount.abs() < Decimal::ONE {
+        amount.round_sf(4).unwrap_or(amount)
+    } else {
+        amount.round_dp(2)
+    };
+    rounded.normalize().to_string()
+}
+
+/// The result of a currency conversion
+#[derive(Debug)]
+pub struct CurrencySuggestion {
+    amount: String,
+    from: String,
+    result: String,
+    to: String,
+    rate: Decimal,
+    date: NaiveDate,
+
+    // how old the rates are, if that's older than the configured maximum
+    stale_days: Option<i64>,
+}
+
+impl Suggestion for CurrencySuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let mut details = row![text(format!(
+            "1 {} = {} {}, rates of {}",
+            self.from,
+            self.rate.round_sf(6).unwrap_or(self.rate).normalize(),
+            self.to,
+            self.date.format("%Y-%m-%d")
+        ))
+        .size(14)
+        .style(theme::Text::Color(Color::from([0.6, 0.6, 0.6])))];
+
+        if let Some(days) = self.stale_days {
+            details = details.push(horizontal_space(Length::Fixed(8f32))).push(
+                text(format!(
+                    "{} days old, `lanch --refresh-rates` updates them",
+                    days
+                ))
+                .size(14)
+                .style(theme::Text::Color(Color::from_rgb8(201, 49, 22))),
+            );
+        }
+
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                column![
+                    text(format!(
+                        "{} {} = {} {}",
+                        self.amount, self.from, self.result, self.to
+                    )),
+                    details,
+                ],
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        Ok(Some(LanchMessage::CopyToClipboard(self.result.clone())))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for CurrencySuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Currency conversion")
+    }
+}
+
+/// Converts currencies with rates from a local file, nothing is fetched while searching
+pub struct CurrencyModule {
+    rates_file: PathBuf,
+    max_age_days: i64,
+
+    // reloaded on cache refreshes, the error is shown once a query asks for a conversion
+    rates: RwLock<Result<Rates, String>>,
+}
+
+impl Default for CurrencyModule {
+    fn default() -> Self {
+        Self {
+            rates_file: PathBuf::new(),
+            max_age_days: DEFAULT_MAX_AGE,
+            rates: RwLock::new(Err(String::from("no exchange rates loaded"))),
+        }
+    }
+}
+
+impl CurrencyModule {
+    fn load_rates(&self) {
+        let rates = Rates::load(&self.rates_file).map_err(|e| {
+            format!(
+                "no exchange rates in {:?} ({}), `lanch --refresh-rates` downloads them",
+                self.rates_file, e
+            )
+        });
+        *self.rates.write().unwrap() = rates;
+    }
+}
+
+impl SuggestionModule for CurrencyModule {
+    fn name(&self) -> &str {
+        "currency"
+    }
+
+    fn description(&self) -> &str {
+        "Converts currencies with offline exchange rates, like \"100 eur to usd\""
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        let config = CurrencyConfig::from_settings(ctx.settings)?;
+        self.rates_file = config.rates_file(&ctx.data_dir);
+        self.max_age_days = config.max_age_days.unwrap_or(DEFAULT_MAX_AGE);
+        self.load_rates();
+        Ok(())
+    }
+
+    fn refresh(&self, _ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        // only picks up a file downloaded in the meantime, refreshing doesn't go online
+        self.load_rates();
+        Ok(())
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (amount, from, to) = match parse(query) {
+            Some(conversion) => conversion,
+            None => return Ok(()),
+        };
+
+        let rates = self.rates.read().unwrap();
+        let rates = match &*rates {
+            Ok(rates) => rates,
+            // "3 cup to tsp" looks the same, so only complain about actual currencies
+            Err(e)
+                if KNOWN_CURRENCIES.contains(&from.as_str())
+                    && KNOWN_CURRENCIES.contains(&to.as_str()) =>
+            {
+                return Err(e.clone().into())
+            }
+            Err(_) => return Ok(()),
+        };
+
+        let (result, rate) = match rates.convert(amount, &from, &to) {
+            Some(conversion) => conversion,
+            None => return Ok(()),
+        };
+
+        let age = (Local::now().date_naive() - rates.date).num_days();
+        v.push((
+            MatchLevel::Exact,
+            Arc::new(CurrencySuggestion {
+                amount: format_amount(amount),
+                result: format_amount(result),
+                from,
+                to,
+                rate,
+                date: rates.date,
+                stale_days: (age > self.max_age_days).then_some(age),
+            }),
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ECB_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gesmes:Envelope xmlns:gesmes="http://www.gesmes.org/xml/2002-08-01">
+  <Cube>
+    <Cube time='2026-10-16'>
+      <Cube currency='USD' rate='1.0765'/>
+      <Cube currency="JPY" rate="161.2"/>
+    </Cube>
+  </Cube>
+</gesmes:Envelope>"#;
+
+    // written out, `Rates::load` takes a path
+    fn load(name: &str, data: &str) -> Result<Rates, Box<dyn std::error::Error>> {
+        let path = std::env::temp_dir().join(format!("lanch-test-{}-{}", std::process::id(), name));
+        fs::write(&path, data)?;
+        let rates = Rates::load(&path);
+        fs::remove_file(&path)?;
+        rates
+    }
+
+    #[test]
+    fn tag_attributes() {
+        let attrs = attributes(" currency='USD' rate=\"1.0765\"/");
+        assert_eq!(attrs.get("currency"), Some(&"USD"));
+        assert_eq!(attrs.get("rate"), Some(&"1.0765"));
+    }
+
+    #[test]
+    fn ecb_xml() {
+        let (date, rates) = parse_ecb_xml(ECB_XML).unwrap();
+        assert_eq!(date, "2026-10-16");
+        assert_eq!(rates["USD"], Decimal::from_str("1.0765").unwrap());
+        assert_eq!(rates["JPY"], Decimal::from_str("161.2").unwrap());
+        assert!(parse_ecb_xml("<html></html>").is_err());
+    }
+
+    #[test]
+    fn converts_through_the_base() {
+        let rates = load("rates.xml", ECB_XML).unwrap();
+        assert_eq!(rates.date, NaiveDate::from_ymd_opt(2026, 10, 16).unwrap());
+
+        let (amount, rate) = rates.convert(Decimal::from(100), "EUR", "USD").unwrap();
+        assert_eq!(amount, Decimal::from_str("107.65").unwrap());
+        assert_eq!(rate, Decimal::from_str("1.0765").unwrap());
+
+        let (amount, _) = rates.convert(Decimal::from(100), "USD", "JPY").unwrap();
+        assert_eq!(format_amount(amount), "14974.45");
+        assert!(rates.convert(Decimal::ONE, "EUR", "XYZ").is_none());
+    }
+
+    #[test]
+    fn json_rates() {
+        let json = r#"{"base": "usd", "date": "2026-10-16", "rates": {"eur": 0.929, "GBP": 0.81}}"#;
+        let rates = load("rates.json", json).unwrap();
+        assert_eq!(rates.rates["USD"], Decimal::ONE);
+        assert_eq!(rates.rates["EUR"], Decimal::from_str("0.929").unwrap());
+
+        assert!(load("bad.json", r#"{"date": "yesterday", "rates": {}}"#).is_err());
+    }
+
+    #[test]
+    fn queries() {
+        let parsed = |query| parse(query).map(|(amount, from, to)| (amount.to_string(), from, to));
+        assert_eq!(
+            parsed("100 eur to usd"),
+            Some((
+                String::from("100"),
+                String::from("EUR"),
+                String::from("USD")
+            ))
+        );
+        assert_eq!(
+            parsed("5 £ in €"),
+            Some((String::from("5"), String::from("GBP"), String::from("EUR")))
+        );
+        assert_eq!(parsed("100 euros to usd"), None);
+        assert_eq!(parsed("eur to usd"), None);
+    }
+
+    #[test]
+    fn amounts() {
+        assert_eq!(
+            format_amount(Decimal::from_str("107.654").unwrap()),
+            "107.65"
+        );
+        assert_eq!(
+            format_amount(Decimal::from_str("0.0062034").unwrap()),
+            "0.006203"
+        );
+    }
+}
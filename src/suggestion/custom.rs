@@ -0,0 +1,175 @@
+use iced::widget::{horizontal_space, row, text};
+use iced::{Element, Length};
+use serde::Deserialize;
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::launcher;
+
+use super::matching::{normalize, SearchKey};
+use super::script::resolve_icon;
+use super::*;
+
+/// An entry of `[[modules.custom.commands]]`
+#[derive(Debug, Deserialize)]
+struct CustomEntry {
+    name: String,
+    command: String,
+
+    // extra words the entry is found by besides its name
+    #[serde(default)]
+    keywords: Vec<String>,
+
+    #[serde(default)]
+    icon: Option<String>,
+
+    // runs the command inside a terminal emulator
+    #[serde(default)]
+    terminal: bool,
+
+    // asks for a second Enter before running the command
+    #[serde(default)]
+    confirm: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CustomConfig {
+    commands: Vec<CustomEntry>,
+}
+
+#[derive(Debug)]
+pub struct CustomSuggestion {
+    name: String,
+    command: String,
+    icon: Option<PathBuf>,
+    terminal: bool,
+    confirm: bool,
+
+    // set by the first Enter on entries that need confirmation
+    confirmed: AtomicBool,
+
+    search_keys: Vec<SearchKey>,
+}
+
+impl CustomSuggestion {
+    fn new(entry: CustomEntry, icon: Option<PathBuf>) -> Self {
+        let mut search_keys = vec![SearchKey::new(&entry.name)];
+        search_keys.extend(entry.keywords.iter().map(|k| SearchKey::new(k)));
+
+        CustomSuggestion {
+            name: entry.name,
+            command: entry.command,
+            icon,
+            terminal: entry.terminal,
+            confirm: entry.confirm,
+            confirmed: AtomicBool::new(false),
+            search_keys,
+        }
+    }
+
+    fn match_normalized(&self, query_norm: &str) -> MatchLevel {
+        self.search_keys
+            .iter()
+            .map(|key| {
+                if key.normalized() == query_norm {
+                    MatchLevel::Exact
+                } else {
+                    key.match_level(query_norm)
+                }
+            })
+            .min()
+            .unwrap_or(MatchLevel::NoMatch)
+    }
+}
+
+impl Suggestion for CustomSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let label = text(format!("{} [{}]", self.name, self.command));
+
+        match self.icon.as_deref().and_then(view_icon) {
+            Some(img) => row![img, horizontal_space(Length::Fixed(5f32)), label].into(),
+            None => label.into(),
+        }
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        if self.confirm && !self.confirmed.swap(true, Ordering::Relaxed) {
+            return Ok(Some(LanchMessage::Notify(format!(
+                " Press Enter again to run \"{}\"",
+                self.command
+            ))));
+        }
+
+        launcher::run(&self.command, self.terminal)?;
+        Ok(None)
+    }
+
+    fn matches(&self, query: &str) -> MatchLevel {
+        self.match_normalized(&normalize(query))
+    }
+}
+
+impl Display for CustomSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Custom")
+    }
+}
+
+/// Commands defined in the config, so small shortcuts don't need a module of their own
+#[derive(Default)]
+pub struct CustomModule {
+    commands: Vec<Arc<CustomSuggestion>>,
+}
+
+impl SuggestionModule for CustomModule {
+    fn name(&self) -> &str {
+        "custom"
+    }
+
+    fn description(&self) -> &str {
+        "Commands defined in the config file"
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        let config: CustomConfig = toml::Value::Table(ctx.settings.clone()).try_into()?;
+
+        self.commands = config
+            .commands
+            .into_iter()
+            .map(|entry| {
+                let icon = entry
+                    .icon
+                    .as_deref()
+                    .and_then(|icon| resolve_icon(icon, Some(ctx.icon_theme)));
+                Arc::new(CustomSuggestion::new(entry, icon))
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let query_norm = normalize(query);
+
+        for cmd in &self.commands {
+            match cmd.match_normalized(&query_norm) {
+                MatchLevel::NoMatch => {}
+                level => {
+                    // a confirmation only counts for the query it was given for
+                    cmd.confirmed.store(false, Ordering::Relaxed);
+                    v.push((level, Arc::clone(cmd) as Arc<dyn Suggestion>));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
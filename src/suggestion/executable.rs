@@ -1,78 +1,111 @@
-use iced::widget::{horizontal_space, image, row, svg, text};
+use iced::widget::{horizontal_space, row, text};
 use iced::{Element, Length};
 use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
-use std::path::PathBuf;
-use std::process::Command;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-use crate::cache::{LanchCache, LanchCacheRc};
+use crate::cache::{LanchCache, SharedCache};
+use crate::launcher;
 
+use super::matching::{normalize, SearchKey};
 use super::*;
 
+// below this many regular matches, candidates with typos are looked for as well
+const TYPO_FALLBACK_THRESHOLD: usize = 3;
+
 // I would use 'Application' but that is already taken by iced
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ProgramSuggestion {
+    // desktop file id ("firefox.desktop"), which is how mimeapps.list refers to programs
+    id: String,
     name: String,
     exec: String,
     icon: Option<PathBuf>,
+
+    // the file types the program can open
+    mime_types: Vec<String>,
+
+    search_key: SearchKey,
 }
 
 impl ProgramSuggestion {
-    pub fn new(name: &str, exec: &str, icon: Option<PathBuf>) -> Self {
+    pub fn new(
+        id: &str,
+        name: &str,
+        exec: &str,
+        icon: Option<PathBuf>,
+        mime_types: Vec<String>,
+    ) -> Self {
         ProgramSuggestion {
+            id: String::from(id),
             name: String::from(name),
             exec: String::from(exec),
             icon,
+            mime_types,
+            search_key: SearchKey::new(name),
         }
     }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn icon(&self) -> Option<&Path> {
+        self.icon.as_deref()
+    }
+
+    pub fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    /// Starts the program with a file or url to open
+    pub fn open(&self, target: &str) -> Result<(), Box<dyn std::error::Error>> {
+        launcher::spawn(&launcher::split_exec(&self.exec, Some(target)))
+    }
+
+    // `query_norm` is the normalized query, so it only has to be computed once per search
+    pub fn match_normalized(&self, query: &str, query_norm: &str) -> MatchLevel {
+        if query == self.name {
+            return MatchLevel::Exact;
+        }
+
+        self.search_key.match_level(query_norm)
+    }
+
+    pub fn match_typos(&self, query: &[char]) -> MatchLevel {
+        self.search_key.match_typos(query)
+    }
 }
 
 impl Suggestion for ProgramSuggestion {
-    fn view(&self) -> Element<LanchMessage> {
-        if let Some(path) = &self.icon {
-            let img: Element<LanchMessage> = match path.extension() {
-                Some(s) => match s.to_str() {
-                    Some("png") | Some("jpg") => image(path).width(20).height(20).into(),
-                    Some("svg") => svg::Svg::from_path(path).width(20).height(20).into(),
-                    _ => return text(self.name.to_string()).into(),
-                },
-                _ => return text(self.name.to_string()).into(),
-            };
-
-            return row![
+    fn view(&self) -> Element<'_, LanchMessage> {
+        if let Some(img) = self.icon.as_deref().and_then(view_icon) {
+            row![
                 img,
                 horizontal_space(Length::Fixed(5f32)),
                 text(self.name.to_string())
             ]
-            .into();
+            .into()
         } else {
-            return text(self.name.to_string()).into();
+            text(self.name.to_string()).into()
         }
     }
 
     fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
-        let mut exec = self.exec.split_whitespace();
-        let mut cmd = Command::new(exec.next().unwrap());
-        // TODO: stopgap for now - this causes some problems with certain args, as they are meant for shells. (ex. "%u")
-        exec.filter(|arg| !arg.starts_with('%')).for_each(|arg| {
-            cmd.arg(arg);
-        });
-        match cmd.spawn() {
-            Ok(_) => Ok(None),
-            Err(e) => Err(Box::new(e)),
-        }
+        launcher::spawn(&launcher::split_exec(&self.exec, None))?;
+        Ok(None)
     }
 
     fn matches(&self, query: &str) -> MatchLevel {
-        if query == self.name {
-            return MatchLevel::Exact;
-        } else if self.name.to_lowercase().contains(&query.to_lowercase()) {
-            return MatchLevel::Contained;
-        }
-
-        MatchLevel::NoMatch
+        self.match_normalized(query, &normalize(query))
     }
 }
 
@@ -86,6 +119,8 @@ impl Display for ProgramSuggestion {
 pub struct ExecutableSuggestion {
     name: String,
     exec: String,
+
+    search_key: SearchKey,
 }
 
 impl ExecutableSuggestion {
@@ -93,33 +128,36 @@ impl ExecutableSuggestion {
         ExecutableSuggestion {
             name: String::from(name),
             exec: String::from(exec),
+            search_key: SearchKey::new(name),
         }
     }
+
+    // `query_norm` is the normalized query, so it only has to be computed once per search
+    pub fn match_normalized(&self, query: &str, query_norm: &str) -> MatchLevel {
+        if query == self.name {
+            return MatchLevel::Exact;
+        }
+
+        self.search_key.match_level(query_norm)
+    }
+
+    pub fn match_typos(&self, query: &[char]) -> MatchLevel {
+        self.search_key.match_typos(query)
+    }
 }
 
 impl Suggestion for ExecutableSuggestion {
-    fn view(&self) -> Element<LanchMessage> {
+    fn view(&self) -> Element<'_, LanchMessage> {
         text(format!("{} [{}]", self.name, self.exec)).into()
     }
 
     fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
-        let mut exec = self.exec.split_whitespace();
-        let mut cmd = Command::new(exec.next().unwrap());
-
-        match cmd.spawn() {
-            Ok(_) => Ok(None),
-            Err(e) => Err(Box::new(e)),
-        }
+        launcher::spawn(std::slice::from_ref(&self.exec))?;
+        Ok(None)
     }
 
     fn matches(&self, query: &str) -> MatchLevel {
-        if query == self.name {
-            return MatchLevel::Exact;
-        } else if self.name.to_lowercase().contains(&query.to_lowercase()) {
-            return MatchLevel::Contained;
-        }
-
-        MatchLevel::NoMatch
+        self.match_normalized(query, &normalize(query))
     }
 }
 
@@ -129,41 +167,192 @@ impl Display for ExecutableSuggestion {
     }
 }
 
+/// Indices into the cache of everything that matched a query. Anything matching a longer
+/// version of that query is among them, so extending the query only has to refine this set.
+struct Candidates {
+    // normalized query
+    query: String,
+    programs: Vec<usize>,
+    executables: Vec<usize>,
+}
+
+#[derive(Default)]
 pub struct ExecutableModule {
-    cache: LanchCacheRc,
+    // written on cache refreshes while queries may still be reading it on worker threads
+    cache: SharedCache,
+
+    // candidates of the last completed search
+    candidates: Mutex<Option<Candidates>>,
+
+    // whether executables in $PATH are searched besides desktop applications
+    executables: bool,
+
+    // normalized keyword -> name of the program it stands for
+    aliases: HashMap<String, String>,
 }
 
 impl ExecutableModule {
-    pub fn new(icon_theme: Option<&str>) -> Self {
+    // the cache gets loaded on init, `cache` can be handed to other modules up front
+    pub fn new(cache: SharedCache) -> Self {
         Self {
-            cache: LanchCache::from_disk_or_new(icon_theme).unwrap().into(),
+            cache,
+            ..Default::default()
         }
     }
-
-    pub fn refresh_cache(&mut self, icon_theme: Option<&str>) {
-        self.cache = LanchCache::new(icon_theme).unwrap().into();
-    }
 }
 
 impl SuggestionModule for ExecutableModule {
-    fn get_matches(&mut self, query: &str, v: &mut VecDeque<Rc<dyn Suggestion>>) {
-        // TODO: we can do better than this efficiency wise
-        self.cache
-            .programs
-            .iter()
-            .for_each(|p| match p.matches(query) {
-                MatchLevel::Exact => v.push_front(Rc::clone(p) as Rc<dyn Suggestion>),
-                MatchLevel::Contained => v.push_back(Rc::clone(p) as Rc<dyn Suggestion>),
+    fn name(&self) -> &str {
+        "programs"
+    }
+
+    fn description(&self) -> &str {
+        "Desktop applications and executables in $PATH"
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        self.executables = match ctx.settings.get("executables") {
+            Some(value) => value.as_bool().ok_or("`executables` has to be a boolean")?,
+            None => true,
+        };
+        if let Some(aliases) = ctx.settings.get("aliases") {
+            let aliases = aliases.as_table().ok_or("`aliases` has to be a table")?;
+            self.aliases = aliases
+                .iter()
+                .map(|(keyword, program)| match program.as_str() {
+                    Some(program) => Ok((normalize(keyword), program.to_string())),
+                    None => Err(format!("alias `{}` has to name a program", keyword)),
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        *self.cache.write().unwrap() = LanchCache::from_disk_or_new(ctx.icon_theme)?.into();
+        Ok(())
+    }
+
+    fn refresh(&self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        let fresh = LanchCache::new(ctx.icon_theme)?.into();
+
+        let mut cache = self.cache.write().unwrap();
+        *cache = fresh;
+        // the indices point into the old cache
+        *self.candidates.lock().unwrap() = None;
+        Ok(())
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // held for the whole search so a refresh can't invalidate the candidate indices
+        let cache = self.cache.read().unwrap();
+        let query_norm = normalize(query);
+
+        let (programs, executables) = match &*self.candidates.lock().unwrap() {
+            Some(prev) if query_norm.starts_with(prev.query.as_str()) => {
+                (prev.programs.clone(), prev.executables.clone())
+            }
+            _ => (
+                (0..cache.programs.len()).collect(),
+                (0..cache.executables.len())
+                    .filter(|_| self.executables)
+                    .collect(),
+            ),
+        };
+
+        let mut next = Candidates {
+            query: query_norm.clone(),
+            programs: Vec::new(),
+            executables: Vec::new(),
+        };
+
+        let alias = self.aliases.get(&query_norm).and_then(|program| {
+            let program = normalize(program);
+            cache
+                .programs
+                .iter()
+                .position(|p| p.search_key.normalized() == program)
+        });
+        if let Some(i) = alias {
+            v.push((
+                MatchLevel::Exact,
+                Arc::clone(&cache.programs[i]) as Arc<dyn Suggestion>,
+            ));
+        }
+
+        for i in programs {
+            if cancel.is_cancelled() {
+                return Ok(());
+            }
+            let p = &cache.programs[i];
+            // already suggested as the alias, still a candidate for longer queries
+            if alias == Some(i) {
+                next.programs.push(i);
+                continue;
+            }
+            match p.match_normalized(query, &query_norm) {
                 MatchLevel::NoMatch => {}
-            });
-
-        self.cache
-            .executables
-            .iter()
-            .for_each(|e| match e.matches(query) {
-                MatchLevel::Exact => v.push_front(Rc::clone(e) as Rc<dyn Suggestion>),
-                MatchLevel::Contained => v.push_back(Rc::clone(e) as Rc<dyn Suggestion>),
+                level => {
+                    next.programs.push(i);
+                    v.push((level, Arc::clone(p) as Arc<dyn Suggestion>));
+                }
+            }
+        }
+
+        for i in executables {
+            if cancel.is_cancelled() {
+                return Ok(());
+            }
+            let e = &cache.executables[i];
+            match e.match_normalized(query, &query_norm) {
                 MatchLevel::NoMatch => {}
-            });
+                level => {
+                    next.executables.push(i);
+                    v.push((level, Arc::clone(e) as Arc<dyn Suggestion>));
+                }
+            }
+        }
+
+        if next.programs.len() + next.executables.len() < TYPO_FALLBACK_THRESHOLD {
+            // typo matches don't narrow down with longer queries, so this always goes over the
+            // whole cache. The distance computation bails out early so that stays cheap.
+            let query_chars: Vec<char> = query_norm.chars().collect();
+
+            for (i, p) in cache.programs.iter().enumerate() {
+                if cancel.is_cancelled() {
+                    return Ok(());
+                }
+                if next.programs.contains(&i) || alias == Some(i) {
+                    continue;
+                }
+                match p.match_typos(&query_chars) {
+                    MatchLevel::NoMatch => {}
+                    level => v.push((level, Arc::clone(p) as Arc<dyn Suggestion>)),
+                }
+            }
+
+            for (i, e) in cache.executables.iter().enumerate() {
+                if cancel.is_cancelled() {
+                    return Ok(());
+                }
+                // the candidates still have to be stored below
+                if !self.executables {
+                    break;
+                }
+                if next.executables.contains(&i) {
+                    continue;
+                }
+                match e.match_typos(&query_chars) {
+                    MatchLevel::NoMatch => {}
+                    level => v.push((level, Arc::clone(e) as Arc<dyn Suggestion>)),
+                }
+            }
+        }
+
+        // only complete searches are reused, a cancelled one would miss candidates
+        *self.candidates.lock().unwrap() = Some(next);
+
+        Ok(())
     }
 }
@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use super::MatchLevel;
+
+// shorter queries would match the initials of way too many names
+const MIN_INITIALS_QUERY_LEN: usize = 2;
+
+// number of typos tolerated for a query of at least the given length (in chars)
+pub const TYPO_BUDGET: [(usize, usize); 2] = [(8, 2), (4, 1)];
+
+/// Brings a string into the form used for matching: compatibility decomposition (full-width
+/// characters, ligatures), diacritics stripped and case folded. Both the query and the
+/// candidates have to go through this for the comparison to make sense.
+pub fn normalize(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+
+    for c in s.nfkd().filter(|c| !is_combining_mark(*c)) {
+        match c {
+            // the few foldings `to_lowercase` doesn't cover
+            'ß' | 'ẞ' => ret.push_str("ss"),
+            'ς' => ret.push('σ'),
+            _ => ret.extend(c.to_lowercase()),
+        }
+    }
+
+    ret
+}
+
+/// The (normalized) first letter of every word in `name`. Words are separated by whitespace,
+/// `-`, `_` and `.`, or start at a camelCase boundary, so "Visual Studio Code" gives "vsc",
+/// "LibreOffice" gives "lo" and "gnome-system-monitor" gives "gsm".
+pub fn initials(name: &str) -> String {
+    let mut ret = String::new();
+    let mut prev: Option<char> = None;
+
+    for c in name.chars() {
+        let word_start = match prev {
+            None => true,
+            Some(p) => {
+                matches!(p, '-' | '_' | '.')
+                    || p.is_whitespace()
+                    || (p.is_lowercase() && c.is_uppercase())
+            }
+        };
+
+        if word_start && c.is_alphanumeric() {
+            ret.push(c);
+        }
+        prev = Some(c);
+    }
+
+    normalize(&ret)
+}
+
+/// Precomputed forms of a name, so matching doesn't allocate for every candidate
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SearchKey {
+    normalized: String,
+    initials: String,
+}
+
+impl SearchKey {
+    pub fn new(name: &str) -> Self {
+        Self {
+            normalized: normalize(name),
+            initials: initials(name),
+        }
+    }
+
+    pub fn normalized(&self) -> &str {
+        &self.normalized
+    }
+
+    // `query_norm` has to be normalized, exact matches are left to the caller
+    pub fn match_level(&self, query_norm: &str) -> MatchLevel {
+        if query_norm.chars().count() >= MIN_INITIALS_QUERY_LEN
+            && self.initials.starts_with(query_norm)
+        {
+            MatchLevel::Initials
+        } else if self.normalized.contains(query_norm) {
+            MatchLevel::Contained
+        } else {
+            MatchLevel::NoMatch
+        }
+    }
+
+    /// Fallback for queries that didn't match otherwise, tolerating a few typos against the
+    /// whole name or any of its words. `query` holds the chars of the normalized query.
+    pub fn match_typos(&self, query: &[char]) -> MatchLevel {
+        let max = match TYPO_BUDGET.iter().find(|(len, _)| query.len() >= *len) {
+            Some((_, max)) => *max,
+            None => return MatchLevel::NoMatch,
+        };
+
+        std::iter::once(self.normalized.as_str())
+            .chain(self.normalized.split(|c: char| !c.is_alphanumeric()))
+            .filter_map(|word| bounded_distance(query, word, max))
+            .min()
+            .map_or(MatchLevel::NoMatch, |d| MatchLevel::Typo(d as u8))
+    }
+}
+
+/// Optimal string alignment distance (Damerau-Levenshtein with adjacent transpositions) between
+/// `a` and `b`, or `None` as soon as it is known to exceed `max`.
+pub fn bounded_distance(a: &[char], b: &str, max: usize) -> Option<usize> {
+    // cheap length check first, most candidates get rejected here
+    let b_len = b.chars().count();
+    if a.len().abs_diff(b_len) > max {
+        return None;
+    }
+
+    let b: Vec<char> = b.chars().collect();
+    let mut prev2: Vec<usize> = vec![0; b.len() + 1];
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut cur: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        cur[0] = i;
+        let mut row_min = cur[0];
+
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            cur[j] = (prev[j] + 1).min(cur[j - 1] + 1).min(prev[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                cur[j] = cur[j].min(prev2[j - 2] + 1);
+            }
+            row_min = row_min.min(cur[j]);
+        }
+
+        // the distance can only grow from here on
+        if row_min > max {
+            return None;
+        }
+
+        std::mem::swap(&mut prev2, &mut prev);
+        std::mem::swap(&mut prev, &mut cur);
+    }
+
+    Some(prev[b.len()]).filter(|d| *d <= max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn normalize_strips_diacritics() {
+        assert_eq!(normalize("Déjà Dup"), "deja dup");
+        assert_eq!(normalize("Čeština"), "cestina");
+        assert_eq!(normalize("Ångström"), "angstrom");
+    }
+
+    #[test]
+    fn normalize_folds_full_width_and_ligatures() {
+        assert_eq!(normalize("ＦＩＲＥＦＯＸ"), "firefox");
+        assert_eq!(normalize("ﬁle manager"), "file manager");
+        assert_eq!(normalize("①"), "1");
+    }
+
+    #[test]
+    fn normalize_folds_case() {
+        assert_eq!(normalize("LibreOffice"), "libreoffice");
+        assert_eq!(normalize("Straße"), "strasse");
+        assert_eq!(normalize("STRAẞE"), "strasse");
+        assert_eq!(normalize("ΟΔΟΣ"), normalize("οδος"));
+        assert_eq!(normalize("ΟΔΟΣ"), normalize("οδοσ"));
+    }
+
+    #[test]
+    fn initials_split_words() {
+        assert_eq!(initials("Visual Studio Code"), "vsc");
+        assert_eq!(initials("LibreOffice"), "lo");
+        assert_eq!(initials("gnome-system-monitor"), "gsm");
+        assert_eq!(initials("org.gnome.Nautilus"), "ogn");
+        assert_eq!(initials("Édition_Rapide"), "er");
+    }
+
+    #[test]
+    fn search_key_levels() {
+        let key = SearchKey::new("Visual Studio Code");
+        assert_eq!(key.normalized(), "visual studio code");
+        assert_eq!(key.match_level("vsc"), MatchLevel::Initials);
+        assert_eq!(key.match_level("vs"), MatchLevel::Initials);
+        assert_eq!(key.match_level("studio"), MatchLevel::Contained);
+        assert_eq!(key.match_level("emacs"), MatchLevel::NoMatch);
+
+        // a single letter would match the initials of too many names
+        assert_eq!(
+            SearchKey::new("Xterm").match_level("q"),
+            MatchLevel::NoMatch
+        );
+    }
+
+    #[test]
+    fn distance_counts_edits() {
+        assert_eq!(bounded_distance(&chars("firefox"), "firefox", 2), Some(0));
+        assert_eq!(bounded_distance(&chars("firefx"), "firefox", 2), Some(1));
+        assert_eq!(bounded_distance(&chars("firfeox"), "firefox", 2), Some(1));
+        assert_eq!(bounded_distance(&chars("fierfx"), "firefox", 2), Some(2));
+        assert_eq!(bounded_distance(&chars("chrome"), "firefox", 2), None);
+
+        // rejected by length alone
+        assert_eq!(bounded_distance(&chars("fox"), "firefox", 2), None);
+    }
+
+    #[test]
+    fn typo_budget_grows_with_the_query() {
+        let key = SearchKey::new("Thunderbird");
+
+        // too short for any typo
+        assert_eq!(key.match_typos(&chars("thn")), MatchLevel::NoMatch);
+
+        assert_eq!(key.match_typos(&chars("thunderbrd")), MatchLevel::Typo(1));
+        assert_eq!(key.match_typos(&chars("thnderbrd")), MatchLevel::Typo(2));
+        assert_eq!(key.match_typos(&chars("thnderbr")), MatchLevel::NoMatch);
+
+        // words are matched on their own as well
+        let key = SearchKey::new("GNOME Terminal");
+        assert_eq!(key.match_typos(&chars("termnal")), MatchLevel::Typo(1));
+    }
+}
@@ -0,0 +1,190 @@
+use iced::widget::{horizontal_space, row, text};
+use iced::{Element, Length};
+use percent_encoding::percent_decode_str;
+
+use std::env;
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::cache::SharedCache;
+use crate::launcher;
+use crate::mime;
+
+use super::executable::ProgramSuggestion;
+use super::*;
+
+/// Something typed into the query that can be opened
+enum Target {
+    Url(String),
+    Path(PathBuf),
+}
+
+impl Target {
+    fn parse(query: &str) -> Option<Self> {
+        if query.contains(char::is_whitespace) && !query.starts_with(['/', '~']) {
+            return None;
+        }
+
+        if let Some(uri) = query.strip_prefix("file://") {
+            // the host part is empty or "localhost" for local files
+            let path = uri.strip_prefix("localhost").unwrap_or(uri);
+            let path = percent_decode_str(path).decode_utf8().ok()?;
+            return Self::existing_path(PathBuf::from(path.as_ref()));
+        }
+
+        if let Some((scheme, rest)) = query.split_once("://") {
+            let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+            if valid_scheme && !rest.is_empty() {
+                return Some(Self::Url(query.to_string()));
+            }
+            return None;
+        }
+
+        if query == "~" || query.starts_with("~/") {
+            let home = PathBuf::from(env::var("HOME").ok()?);
+            return Self::existing_path(home.join(query[1..].trim_start_matches('/')));
+        }
+        if query.starts_with('/') {
+            return Self::existing_path(PathBuf::from(query));
+        }
+
+        None
+    }
+
+    fn existing_path(path: PathBuf) -> Option<Self> {
+        path.exists().then_some(Self::Path(path))
+    }
+
+    fn handlers(&self, programs: &[Arc<ProgramSuggestion>]) -> mime::Handlers {
+        match self {
+            Target::Url(url) => mime::for_url(url)
+                .map(|mime| mime::handlers(&mime, programs))
+                .unwrap_or_default(),
+            Target::Path(path) => mime::handlers_for_path(path, programs),
+        }
+    }
+
+    // what gets passed to the program opening the target
+    fn argument(&self) -> String {
+        match self {
+            Target::Url(url) => url.clone(),
+            Target::Path(path) => path.to_string_lossy().into_owned(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OpenSuggestion {
+    target: String,
+
+    // None leaves the choice to xdg-open
+    program: Option<Arc<ProgramSuggestion>>,
+
+    // the program is the one the user picked for the file type
+    default: bool,
+}
+
+impl Suggestion for OpenSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let label = match (&self.program, self.default) {
+            (Some(program), true) => text(format!("Open {} with {}", self.target, program.name())),
+            (Some(program), false) => text(format!("Open with {}", program.name())),
+            (None, _) => text(format!("Open {} with the default application", self.target)),
+        };
+
+        match self
+            .program
+            .as_ref()
+            .and_then(|p| p.icon())
+            .and_then(view_icon)
+        {
+            Some(img) => row![img, horizontal_space(Length::Fixed(5f32)), label].into(),
+            None => label.into(),
+        }
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        match &self.program {
+            Some(program) => program.open(&self.target)?,
+            None => launcher::open(&self.target)?,
+        }
+        Ok(None)
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for OpenSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Open")
+    }
+}
+
+/// Opens urls and files typed into the query, with the program picked in mimeapps.list or any
+/// other one that can handle them
+pub struct OpenModule {
+    cache: SharedCache,
+}
+
+impl OpenModule {
+    pub fn new(cache: SharedCache) -> Self {
+        Self { cache }
+    }
+}
+
+impl SuggestionModule for OpenModule {
+    fn name(&self) -> &str {
+        "open"
+    }
+
+    fn description(&self) -> &str {
+        "Opens urls and file paths typed into the search"
+    }
+
+    fn refresh(&self, _ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        mime::reload_mimeapps();
+        Ok(())
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let target = match Target::parse(query.trim()) {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        let argument = target.argument();
+        let handlers = target.handlers(&self.cache.read().unwrap().programs);
+
+        v.push((
+            MatchLevel::Exact,
+            Arc::new(OpenSuggestion {
+                target: argument.clone(),
+                default: handlers.default.is_some(),
+                program: handlers.default,
+            }),
+        ));
+
+        for program in handlers.others {
+            v.push((
+                MatchLevel::Contained,
+                Arc::new(OpenSuggestion {
+                    target: argument.clone(),
+                    program: Some(program),
+                    default: false,
+                }),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Native plugins, shared libraries built against `lanch-plugin-sdk` and loaded from
+//! `~/.config/lanch/plugins`. Unlike script modules they run inside lanch, so a crashing plugin
+//! takes lanch down with it.
+
+use lanch_plugin_sdk::{str_from_ptr, PluginVTable, RawItem, ABI_VERSION, ENTRY_SYMBOL};
+use libloading::Library;
+
+use std::ffi::{c_void, CString};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::config::CONFIG_DIR;
+use crate::icons::IconTheme;
+
+use super::script::{resolve_icon, score_level, ScriptAction, ScriptSuggestion};
+use super::*;
+
+pub struct PluginModule {
+    vtable: &'static PluginVTable,
+
+    // null until `init` and after `shutdown`. Queries hold the read lock while inside the
+    // plugin so the state can't be freed under them.
+    state: RwLock<*mut c_void>,
+
+    name: String,
+    description: String,
+    icon_theme: Option<IconTheme>,
+
+    // keeps the code `vtable` points into loaded, fields are dropped in order so this goes last
+    _lib: Library,
+}
+
+// the sdk requires plugins to be `Send + Sync`
+unsafe impl Send for PluginModule {}
+unsafe impl Sync for PluginModule {}
+
+// collects the items pushed by a plugin during `get_matches`
+struct Collector<'a> {
+    module: &'a PluginModule,
+    matches: Vec<(u8, Match)>,
+}
+
+extern "C" fn push_item(ctx: *mut c_void, item: *const RawItem) {
+    let collector = unsafe { &mut *(ctx as *mut Collector) };
+    let item = unsafe { &*item };
+    let string = |s| unsafe { str_from_ptr(s) }.to_string();
+    let optional = |s| Some(string(s)).filter(|s: &String| !s.is_empty());
+
+    let module = collector.module;
+    let icon = optional(item.icon).and_then(|icon| resolve_icon(&icon, module.icon_theme.as_ref()));
+    let actions = optional(item.exec)
+        .map(|exec| ScriptAction {
+            label: String::from("Run"),
+            exec,
+        })
+        .into_iter()
+        .collect();
+
+    let suggestion = ScriptSuggestion::new(
+        &module.name,
+        string(item.title),
+        optional(item.subtitle),
+        icon,
+        actions,
+    );
+    collector
+        .matches
+        .push((item.score, (score_level(item.score), Arc::new(suggestion))));
+}
+
+impl PluginModule {
+    /// Loads every shared library in `~/.config/lanch/plugins`. Libraries that fail to load are
+    /// reported and skipped.
+    pub fn discover() -> Vec<Result<Self, String>> {
+        let dir = match fs::read_dir(CONFIG_DIR.join("plugins")) {
+            Ok(dir) => dir,
+            Err(_) => return Vec::new(),
+        };
+
+        dir.flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "so"))
+            .map(|path| Self::load(&path).map_err(|e| format!("{}: {}", path.display(), e)))
+            .collect()
+    }
+
+    fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        // the plugin is trusted as much as lanch itself, it is the user's own plugin directory
+        let lib = unsafe { Library::new(path)? };
+        let vtable: &'static PluginVTable = unsafe {
+            let symbol = lib.get::<*const PluginVTable>(ENTRY_SYMBOL)?;
+            &**symbol
+        };
+        Self::from_vtable(lib, vtable)
+    }
+
+    // `vtable` has to point into `lib`
+    fn from_vtable(
+        lib: Library,
+        vtable: &'static PluginVTable,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        if vtable.abi_version != ABI_VERSION {
+            return Err(format!(
+                "built for plugin abi {}, lanch uses {}",
+                vtable.abi_version, ABI_VERSION
+            )
+            .into());
+        }
+
+        let name = unsafe { str_from_ptr((vtable.name)()) }.to_string();
+        if name.is_empty() {
+            return Err("plugin has no name".into());
+        }
+
+        Ok(Self {
+            vtable,
+            state: RwLock::new(std::ptr::null_mut()),
+            name,
+            description: String::new(),
+            icon_theme: None,
+            _lib: lib,
+        })
+    }
+}
+
+impl SuggestionModule for PluginModule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        let settings = CString::new(serde_json::to_string(ctx.settings)?)?;
+        let state = (self.vtable.init)(settings.as_ptr());
+        if state.is_null() {
+            return Err("plugin failed to initialize".into());
+        }
+
+        *self.state.get_mut().unwrap() = state;
+        self.description = unsafe { str_from_ptr((self.vtable.description)(state)) }.to_string();
+        self.icon_theme = Some(ctx.icon_theme.clone());
+
+        Ok(())
+    }
+
+    fn shutdown(&self) {
+        let mut state = self.state.write().unwrap();
+        if !state.is_null() {
+            (self.vtable.shutdown)(*state);
+            *state = std::ptr::null_mut();
+        }
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let query = CString::new(query)?;
+
+        let state = self.state.read().unwrap();
+        if state.is_null() {
+            return Ok(());
+        }
+
+        let mut collector = Collector {
+            module: self,
+            matches: Vec::new(),
+        };
+        (self.vtable.get_matches)(
+            *state,
+            query.as_ptr(),
+            push_item,
+            &mut collector as *mut Collector as *mut c_void,
+        );
+
+        if !cancel.is_cancelled() {
+            let mut matches = collector.matches;
+            matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            v.extend(matches.into_iter().map(|(_, m)| m));
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for PluginModule {
+    // `shutdown` clears the state, so this only frees the state of plugins that weren't shut
+    // down by the registry
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModuleConfig;
+    use crate::suggestion::registry::ModuleRegistry;
+    use lanch_plugin_sdk::{export_plugin, Item, Plugin};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    // the settings of the plugins that were shut down
+    static SHUT_DOWN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[derive(Default)]
+    struct Greeter {
+        greeting: String,
+    }
+
+    impl Plugin for Greeter {
+        fn name(&self) -> &str {
+            "greeter"
+        }
+
+        fn init(&mut self, settings: &str) {
+            self.greeting = settings.to_string();
+        }
+
+        fn get_matches(&self, query: &str, out: &mut Vec<Item>) {
+            out.push(Item::new(format!("{} {}", self.greeting, query)));
+        }
+
+        fn shutdown(&mut self) {
+            SHUT_DOWN.lock().unwrap().push(self.greeting.clone());
+        }
+    }
+
+    export_plugin!(Greeter);
+
+    // the plugin above, as if loaded from a library
+    fn greeter() -> PluginModule {
+        let lib = libloading::os::unix::Library::this().into();
+        PluginModule::from_vtable(lib, &__lanch_plugin::lanch_plugin).unwrap()
+    }
+
+    fn shut_down(settings: &str) -> usize {
+        SHUT_DOWN
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.contains(settings))
+            .count()
+    }
+
+    fn init(module: &mut PluginModule, settings: &str) {
+        let settings: toml::Table = toml::from_str(settings).unwrap();
+        let config = ModuleConfig {
+            settings,
+            ..Default::default()
+        };
+        let icon_theme = IconTheme {
+            name: None,
+            scale: 1,
+        };
+        let ctx = ModuleContext {
+            settings: &config.settings,
+            icon_theme: &icon_theme,
+            data_dir: std::env::temp_dir(),
+        };
+        module.init(&ctx).unwrap();
+    }
+
+    #[test]
+    fn config_is_found_by_the_declared_name() {
+        let module = greeter();
+        assert_eq!(module.name(), "greeter");
+
+        let config: HashMap<String, ModuleConfig> =
+            toml::from_str("[greeter]\ngreeting = 'hello'\ntrigger = 'gr'").unwrap();
+        let icon_theme = IconTheme {
+            name: None,
+            scale: 1,
+        };
+        let mut registry = ModuleRegistry::new(icon_theme, config);
+        registry.register(Box::new(module)).unwrap();
+
+        let route = registry.route("gr world");
+        let (m, query) = route.targets[0];
+        assert_eq!(m.module.name(), "greeter");
+
+        let mut matches = Vec::new();
+        m.module
+            .get_matches(query, &CancelToken::default(), &mut matches)
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        let suggestion = format!("{:?}", matches[0].1);
+        assert!(suggestion.contains(r#"title: "{\"greeting\":\"hello\"} world""#));
+    }
+
+    #[test]
+    fn state_is_freed_once() {
+        let mut module = greeter();
+        init(&mut module, "id = 'dropped'");
+        drop(module);
+        assert_eq!(shut_down("dropped"), 1);
+
+        let mut module = greeter();
+        init(&mut module, "id = 'shut down'");
+        module.shutdown();
+        drop(module);
+        assert_eq!(shut_down("shut down"), 1);
+
+        // never initialized, nothing to free
+        drop(greeter());
+    }
+
+    #[test]
+    fn abi_mismatch() {
+        static OLD: PluginVTable = PluginVTable {
+            abi_version: 0,
+            ..__lanch_plugin::lanch_plugin
+        };
+        let lib = libloading::os::unix::Library::this().into();
+        let error = PluginModule::from_vtable(lib, &OLD).err().unwrap();
+        assert!(error.to_string().contains("plugin abi 0"));
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::config::{data_home, ModuleConfig};
+use crate::icons::IconTheme;
+
+use super::{ModuleContext, ModuleWorker, SuggestionModule};
+
+/// A loaded module along with its (possibly user configured) settings
+pub struct RegisteredModule {
+    pub module: Arc<dyn SuggestionModule>,
+    pub worker: ModuleWorker,
+    config: ModuleConfig,
+    trigger: Option<String>,
+    global: bool,
+    enabled: bool,
+}
+
+impl RegisteredModule {
+    pub fn trigger(&self) -> Option<&str> {
+        self.trigger.as_deref()
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // the rest of the query if it starts with the trigger of this module
+    fn strip_trigger<'a>(&self, query: &'a str) -> Option<&'a str> {
+        let trigger = self.trigger.as_deref()?;
+        let rest = query.strip_prefix(trigger)?;
+
+        // keywords have to stand on their own ("time" shouldn't trigger on "timeshift"),
+        // prefixes like "!" don't
+        let is_keyword = trigger.ends_with(char::is_alphanumeric);
+        if is_keyword && !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        Some(rest.trim_start())
+    }
+}
+
+// an existing absolute path, which can start with a trigger like "/" as well
+fn is_absolute_path(query: &str) -> bool {
+    query.len() > 1 && query.starts_with('/') && Path::new(query).exists()
+}
+
+/// Where a query should go
+pub struct Route<'a> {
+    // the triggered module, if any
+    pub active: Option<&'a RegisteredModule>,
+
+    // the modules to query, along with the query they get to see
+    pub targets: Vec<(&'a RegisteredModule, &'a str)>,
+}
+
+/// Keeps track of the loaded modules, drives their lifecycle and decides which of them get to
+/// see a query
+pub struct ModuleRegistry {
+    modules: Vec<RegisteredModule>,
+    icon_theme: IconTheme,
+
+    // config sections of the modules that haven't been registered yet
+    config: HashMap<String, ModuleConfig>,
+}
+
+impl ModuleRegistry {
+    pub fn new(icon_theme: IconTheme, config: HashMap<String, ModuleConfig>) -> Self {
+        Self {
+            modules: Vec::new(),
+            icon_theme,
+            config,
+        }
+    }
+
+    fn context<'a>(&'a self, name: &str, config: &'a ModuleConfig) -> ModuleContext<'a> {
+        ModuleContext {
+            settings: &config.settings,
+            icon_theme: &self.icon_theme,
+            data_dir: data_home().join("lanch").join(name),
+        }
+    }
+
+    /// Initializes the module with its config section and adds it to the registry
+    pub fn register(
+        &mut self,
+        mut module: Box<dyn SuggestionModule>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let config = self.config.remove(module.name()).unwrap_or_default();
+
+        module
+            .init(&self.context(module.name(), &config))
+            .map_err(|e| format!("{}: {}", module.name(), e))?;
+
+        let trigger = match config.trigger.as_deref() {
+            Some("") => None,
+            Some(trigger) => Some(trigger.to_string()),
+            None => module.trigger().map(String::from),
+        };
+        let global = config.global.unwrap_or_else(|| module.global());
+        let enabled = config.enabled.unwrap_or(true);
+
+        let module: Arc<dyn SuggestionModule> = module.into();
+        self.modules.push(RegisteredModule {
+            worker: ModuleWorker::new(Arc::clone(&module)),
+            module,
+            config,
+            trigger,
+            global,
+            enabled,
+        });
+
+        Ok(())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &RegisteredModule> {
+        self.modules.iter()
+    }
+
+    pub fn set_enabled(&mut self, idx: usize, enabled: bool) {
+        if let Some(m) = self.modules.get_mut(idx) {
+            m.enabled = enabled;
+        }
+    }
+
+    /// Asks every module to regenerate its cached data, collecting the errors
+    pub fn refresh(&self) -> Vec<String> {
+        self.modules
+            .iter()
+            .filter_map(|m| {
+                m.module
+                    .refresh(&self.context(m.module.name(), &m.config))
+                    .err()
+                    .map(|e| format!("{}: {}", m.module.name(), e))
+            })
+            .collect()
+    }
+
+    pub fn shutdown(&self) {
+        self.modules.iter().for_each(|m| m.module.shutdown());
+    }
+
+    pub fn route<'a>(&'a self, query: &'a str) -> Route<'a> {
+        let enabled = self.modules.iter().filter(|m| m.enabled);
+
+        // the longest trigger wins, so "!!" can be told apart from "!"
+        let triggered = enabled
+            .clone()
+            .filter_map(|m| m.strip_trigger(query).map(|rest| (m, rest)))
+            .max_by_key(|(m, _)| m.trigger.as_ref().map_or(0, String::len));
+
+        match triggered {
+            // "/etc/hosts" is a path to open rather than a command for the builtin module
+            Some((m, rest)) if is_absolute_path(query) => Route {
+                active: None,
+                targets: enabled
+                    .filter(|other| other.global && !Arc::ptr_eq(&other.module, &m.module))
+                    .map(|other| (other, query))
+                    .chain([(m, rest)])
+                    .collect(),
+            },
+            Some((m, rest)) => Route {
+                active: Some(m),
+                targets: vec![(m, rest)],
+            },
+            None => Route {
+                active: None,
+                targets: enabled.filter(|m| m.global).map(|m| (m, query)).collect(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::SharedCache;
+    use crate::suggestion::builtin::BuiltInModule;
+    use crate::suggestion::open::OpenModule;
+    use crate::suggestion::CancelToken;
+
+    fn registry() -> ModuleRegistry {
+        let icon_theme = IconTheme {
+            name: None,
+            scale: 1,
+        };
+        let mut registry = ModuleRegistry::new(icon_theme, HashMap::new());
+        registry
+            .register(Box::new(OpenModule::new(SharedCache::default())))
+            .unwrap();
+        registry.register(Box::new(BuiltInModule::new())).unwrap();
+        registry
+    }
+
+    fn target_names(route: &Route) -> Vec<String> {
+        route
+            .targets
+            .iter()
+            .map(|(m, _)| m.module.name().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn absolute_paths_reach_the_open_module() {
+        let registry = registry();
+        let route = registry.route("/etc/hosts");
+        assert!(route.active.is_none());
+        assert!(target_names(&route).contains(&String::from("open")));
+
+        let (open, query) = route
+            .targets
+            .iter()
+            .find(|(m, _)| m.module.name() == "open")
+            .unwrap();
+        let mut matches = Vec::new();
+        open.module
+            .get_matches(query, &CancelToken::default(), &mut matches)
+            .unwrap();
+        assert!(!matches.is_empty());
+    }
+
+    #[test]
+    fn builtin_commands_stay_triggered() {
+        let registry = registry();
+        let route = registry.route("/refresh cache");
+        assert_eq!(route.active.map(|m| m.module.name()), Some("builtin"));
+        assert_eq!(target_names(&route), ["builtin"]);
+    }
+}
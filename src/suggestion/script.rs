@@ -0,0 +1,447 @@
+//! Modules implemented as external programs, so they can be written in any language.
+//!
+//! For every query the program is started with `{"query": "..."}` (plus a newline) on stdin,
+//! and answers with one JSON object per line on stdout:
+//!
+//! ```json
+//! {"title": "Firefox", "subtitle": "web browser", "icon": "firefox", "score": 80,
+//!  "actions": [{"label": "Open", "exec": "firefox"}]}
+//! ```
+//!
+//! Everything but `title` is optional. `icon` is an icon name or a path, `score` ranges from
+//! 0 to 100 (100 being an exact match, 50 and up as good as a substring match) and Enter runs
+//! the first action. The program gets killed once it exceeds its timeout or the query changes.
+
+use iced::widget::{column, horizontal_space, row, text};
+use iced::{theme, Color, Element, Length};
+use serde::{Deserialize, Serialize};
+
+use std::fmt::Display;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config::CONFIG_DIR;
+use crate::icons::IconTheme;
+use crate::launcher;
+
+use super::*;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_millis(500);
+
+// how often a running script checks whether its query got cancelled
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Serialize)]
+struct ScriptQuery<'a> {
+    query: &'a str,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptAction {
+    pub label: String,
+    pub exec: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScriptResult {
+    title: String,
+    #[serde(default)]
+    subtitle: Option<String>,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    actions: Vec<ScriptAction>,
+    #[serde(default)]
+    score: u8,
+}
+
+/// A suggestion described by an external module, also used by native plugins
+#[derive(Debug)]
+pub struct ScriptSuggestion {
+    module: String,
+    title: String,
+    subtitle: Option<String>,
+    icon: Option<PathBuf>,
+    actions: Vec<ScriptAction>,
+}
+
+impl ScriptSuggestion {
+    pub fn new(
+        module: &str,
+        title: String,
+        subtitle: Option<String>,
+        icon: Option<PathBuf>,
+        actions: Vec<ScriptAction>,
+    ) -> Self {
+        Self {
+            module: module.to_string(),
+            title,
+            subtitle,
+            icon,
+            actions,
+        }
+    }
+}
+
+/// Layout shared by the suggestions of external modules. The label of the action Enter runs
+/// goes next to the title.
+pub fn view_item<'a>(
+    title: &'a str,
+    label: Option<&'a str>,
+    subtitle: Option<&'a str>,
+    icon: Option<&Path>,
+) -> Element<'a, LanchMessage> {
+    let title: Element<LanchMessage> = match label {
+        Some(label) => row![
+            text(title),
+            horizontal_space(Length::Fixed(8f32)),
+            text(format!("({})", label)).style(theme::Text::Color(Color::from([0.6, 0.6, 0.6]))),
+        ]
+        .into(),
+        None => text(title).into(),
+    };
+
+    let mut txt = column![title];
+    if let Some(subtitle) = subtitle {
+        txt = txt.push(
+            text(subtitle)
+                .size(14)
+                .style(theme::Text::Color(Color::from([0.6, 0.6, 0.6]))),
+        );
+    }
+
+    match icon.and_then(view_icon) {
+        Some(img) => row![img, horizontal_space(Length::Fixed(5f32)), txt].into(),
+        None => txt.into(),
+    }
+}
+
+impl Suggestion for ScriptSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        view_item(
+            &self.title,
+            self.actions.first().map(|a| a.label.as_str()),
+            self.subtitle.as_deref(),
+            self.icon.as_deref(),
+        )
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        let action = self
+            .actions
+            .first()
+            .ok_or_else(|| format!("{} has no action", self.title))?;
+
+        launcher::run(&action.exec, false)?;
+        Ok(None)
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        // the script did the matching already
+        MatchLevel::Contained
+    }
+}
+
+impl Display for ScriptSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.module)
+    }
+}
+
+pub fn score_level(score: u8) -> MatchLevel {
+    match score {
+        100.. => MatchLevel::Exact,
+        50..=99 => MatchLevel::Contained,
+        _ => MatchLevel::Typo(1),
+    }
+}
+
+/// Icons of external modules are given either as a path or as a name to look up in the theme
+pub fn resolve_icon(icon: &str, icon_theme: Option<&IconTheme>) -> Option<PathBuf> {
+    let path = PathBuf::from(icon);
+    if path.is_absolute() {
+        Some(path)
+    } else {
+        icon_theme?.lookup(icon)
+    }
+}
+
+pub struct ScriptModule {
+    name: String,
+    path: PathBuf,
+    description: String,
+    timeout: Duration,
+    icon_theme: Option<IconTheme>,
+}
+
+impl ScriptModule {
+    /// Every executable in `~/.config/lanch/modules` is a module named after the file
+    pub fn discover() -> Vec<Self> {
+        let dir = match fs::read_dir(CONFIG_DIR.join("modules")) {
+            Ok(dir) => dir,
+            Err(_) => return Vec::new(),
+        };
+
+        dir.flatten()
+            .filter(|entry| {
+                entry
+                    .metadata()
+                    .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                    .unwrap_or(false)
+            })
+            .filter_map(|entry| {
+                let path = entry.path();
+                let name = path.file_stem()?.to_str()?.to_string();
+
+                Some(Self {
+                    description: format!("Script module {}", path.display()),
+                    name,
+                    path,
+                    timeout: DEFAULT_TIMEOUT,
+                    icon_theme: None,
+                })
+            })
+            .collect()
+    }
+
+    // the parsed result along with its score
+    fn parse_line(&self, line: &str) -> Option<(u8, Match)> {
+        let result: ScriptResult = match serde_json::from_str(line) {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("[SCRIPT] {}: invalid result {:?}: {}", self.name, line, e);
+                return None;
+            }
+        };
+
+        let icon = result
+            .icon
+            .and_then(|icon| resolve_icon(&icon, self.icon_theme.as_ref()));
+
+        let suggestion = ScriptSuggestion::new(
+            &self.name,
+            result.title,
+            result.subtitle,
+            icon,
+            result.actions,
+        );
+
+        Some((
+            result.score,
+            (score_level(result.score), Arc::new(suggestion)),
+        ))
+    }
+}
+
+impl SuggestionModule for ScriptModule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(timeout) = ctx.settings.get("timeout_ms") {
+            let ms = timeout
+                .as_integer()
+                .filter(|ms| *ms > 0)
+                .ok_or("`timeout_ms` has to be a positive integer")?;
+            self.timeout = Duration::from_millis(ms as u64);
+        }
+        if let Some(description) = ctx.settings.get("description").and_then(|d| d.as_str()) {
+            self.description = description.to_string();
+        }
+        self.icon_theme = Some(ctx.icon_theme.clone());
+
+        Ok(())
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut child = match Command::new(&self.path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => return Err(format!("failed to start: {}", e).into()),
+        };
+
+        // a script that doesn't read its input would make this fail, that's fine
+        if let Some(mut stdin) = child.stdin.take() {
+            let input = serde_json::to_string(&ScriptQuery { query }).unwrap();
+            let _ = writeln!(stdin, "{input}");
+        }
+
+        // read on a separate thread so the timeout still applies to a script that hangs
+        let (tx, rx) = mpsc::channel();
+        let stdout = child.stdout.take().unwrap();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let deadline = Instant::now() + self.timeout;
+        let mut results = Vec::new();
+        let mut timed_out = false;
+        loop {
+            if cancel.is_cancelled() {
+                break;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                timed_out = true;
+                break;
+            }
+
+            match rx.recv_timeout(remaining.min(POLL_INTERVAL)) {
+                Ok(line) if line.trim().is_empty() => {}
+                Ok(line) => results.extend(self.parse_line(&line)),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                // stdout closed, the script is done (or crashed)
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        let _ = child.kill();
+        let _ = child.wait();
+
+        if !cancel.is_cancelled() {
+            results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            v.extend(results.into_iter().map(|(_, m)| m));
+        }
+
+        if timed_out {
+            return Err(format!("timed out after {}ms", self.timeout.as_millis()).into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(name: &str, path: PathBuf, timeout: Duration) -> ScriptModule {
+        ScriptModule {
+            name: name.to_string(),
+            path,
+            description: String::new(),
+            timeout,
+            icon_theme: None,
+        }
+    }
+
+    // runs `script` as a module for `query`, giving the matches in their debug form
+    fn run(
+        name: &str,
+        script: &str,
+        timeout: Duration,
+        query: &str,
+    ) -> (Vec<String>, Option<String>) {
+        let path = std::env::temp_dir().join(format!("lanch-test-{}-{}", std::process::id(), name));
+        fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut matches = Vec::new();
+        let result = module(name, path.clone(), timeout).get_matches(
+            query,
+            &CancelToken::default(),
+            &mut matches,
+        );
+        fs::remove_file(&path).unwrap();
+
+        let matches = matches
+            .iter()
+            .map(|(level, sg)| format!("{:?} {:?}", level, sg))
+            .collect();
+        (matches, result.err().map(|e| e.to_string()))
+    }
+
+    #[test]
+    fn result_lines() {
+        let module = module("lines", PathBuf::new(), DEFAULT_TIMEOUT);
+
+        let (score, (level, sg)) = module
+            .parse_line(
+                r#"{"title": "Firefox", "subtitle": "web browser", "icon": "/firefox.png",
+                    "score": 100, "actions": [{"label": "Open", "exec": "firefox"}]}"#,
+            )
+            .unwrap();
+        assert_eq!((score, level), (100, MatchLevel::Exact));
+        let sg = format!("{:?}", sg);
+        assert!(sg.contains(r#"subtitle: Some("web browser")"#));
+        assert!(sg.contains(r#"icon: Some("/firefox.png")"#));
+        assert!(sg.contains(r#"exec: "firefox""#));
+
+        let (score, (level, _)) = module.parse_line(r#"{"title": "Firefox"}"#).unwrap();
+        assert_eq!((score, level), (0, MatchLevel::Typo(1)));
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let module = module("malformed", PathBuf::new(), DEFAULT_TIMEOUT);
+        assert!(module.parse_line("Firefox").is_none());
+        assert!(module.parse_line(r#"{"subtitle": "no title"}"#).is_none());
+        assert!(module
+            .parse_line(r#"{"title": "Firefox", "score": 300}"#)
+            .is_none());
+        assert!(module.parse_line(r#"{"title": "Firefox""#).is_none());
+    }
+
+    #[test]
+    fn scripts_get_the_query() {
+        // {"query":"..."} comes back as {"title":"..."}, the lines in between are skipped
+        let script = r#"sed 's/"query"/"title"/'
+echo
+echo 'not json'
+echo '{"title": "best", "score": 100}'"#;
+        let (matches, error) = run("echo", script, Duration::from_secs(5), "fire \"fox\"");
+        assert_eq!(error, None);
+        assert_eq!(matches.len(), 2);
+        // sorted by score
+        assert!(matches[0].starts_with("Exact"));
+        assert!(matches[0].contains(r#"title: "best""#));
+        assert!(matches[1].contains(r#"title: "fire \"fox\"""#));
+    }
+
+    #[test]
+    fn slow_scripts_are_killed() {
+        let script = r#"echo '{"title": "early", "score": 60}'
+exec sleep 10"#;
+
+        let start = Instant::now();
+        let (matches, error) = run("slow", script, Duration::from_millis(200), "x");
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(error.as_deref(), Some("timed out after 200ms"));
+        // what came in before the timeout is still shown
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].contains(r#"title: "early""#));
+    }
+
+    #[test]
+    fn missing_scripts() {
+        let module = module("missing", PathBuf::from("/nonexistent"), DEFAULT_TIMEOUT);
+        let mut matches = Vec::new();
+        let error = module
+            .get_matches("x", &CancelToken::default(), &mut matches)
+            .unwrap_err();
+        assert!(error.to_string().starts_with("failed to start"));
+    }
+}
@@ -0,0 +1,464 @@
+//! Modules written in Rhai, for shortcuts too small to be worth a script module.
+//!
+//! Every `*.rhai` file in `~/.config/lanch/scripts` is run once on startup and registers any
+//! number of providers:
+//!
+//! ```rhai
+//! register_provider(#{
+//!     name: "greet",
+//!     description: "Says hello",
+//!     trigger: "hi",
+//!     global: false,
+//!     matches: |query| [
+//!         #{ title: `Hello ${query}`, subtitle: "copy the greeting", score: 80,
+//!            action: copy(`Hello ${query}`) },
+//!     ],
+//! });
+//! ```
+//!
+//! `matches` gets the query and returns a list of items. Everything but `title` is optional,
+//! `icon` and `score` work like they do for script modules. Actions are built with `run(cmd)`,
+//! `copy(text)`, `open_url(url)` and `set_query(text)`, an item can override the label shown
+//! next to its title with `label`. `score(text, query)` ranks a text like the builtin modules
+//! do, from 0 (no match) to 100 (exact match).
+
+use rhai::{Array, Dynamic, Engine, FnPtr, Map, AST};
+
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::config::CONFIG_DIR;
+use crate::icons::IconTheme;
+use crate::launcher;
+
+use super::matching::{normalize, SearchKey};
+use super::script::{resolve_icon, score_level, view_item};
+use super::*;
+
+// keeps an endless loop in a script from hanging its module thread forever
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// What happens when a script suggestion is executed
+#[derive(Debug, Clone)]
+enum Action {
+    Run(String),
+    Copy(String),
+    OpenUrl(String),
+    SetQuery(String),
+}
+
+impl Action {
+    fn label(&self) -> &str {
+        match self {
+            Action::Run(_) => "run",
+            Action::Copy(_) => "copy",
+            Action::OpenUrl(_) => "open",
+            Action::SetQuery(_) => "complete",
+        }
+    }
+}
+
+// a script along with the engine that compiled it, shared by all of its providers
+struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+#[derive(Debug)]
+pub struct RhaiSuggestion {
+    module: String,
+    title: String,
+    label: Option<String>,
+    subtitle: Option<String>,
+    icon: Option<PathBuf>,
+    action: Option<Action>,
+}
+
+impl Suggestion for RhaiSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let label = self
+            .label
+            .as_deref()
+            .or_else(|| self.action.as_ref().map(Action::label));
+
+        view_item(
+            &self.title,
+            label,
+            self.subtitle.as_deref(),
+            self.icon.as_deref(),
+        )
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        match self.action.as_ref() {
+            Some(Action::Run(cmd)) => {
+                launcher::run(cmd, false)?;
+                Ok(None)
+            }
+            Some(Action::OpenUrl(url)) => {
+                launcher::open(url)?;
+                Ok(None)
+            }
+            Some(Action::Copy(text)) => Ok(Some(LanchMessage::CopyToClipboard(text.clone()))),
+            Some(Action::SetQuery(query)) => Ok(Some(LanchMessage::SetQuery(query.clone()))),
+            None => Err(format!("{} has no action", self.title).into()),
+        }
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        // the script did the matching already
+        MatchLevel::Contained
+    }
+}
+
+impl Display for RhaiSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.module)
+    }
+}
+
+pub struct RhaiModule {
+    name: String,
+    description: String,
+    trigger: Option<String>,
+    global: bool,
+    matches: FnPtr,
+    script: Arc<Script>,
+    icon_theme: Option<IconTheme>,
+}
+
+impl RhaiModule {
+    /// Runs every script in `~/.config/lanch/scripts`, a script that fails to compile or run
+    /// shows up as an error
+    pub fn discover() -> Vec<Result<Self, String>> {
+        let dir = match fs::read_dir(CONFIG_DIR.join("scripts")) {
+            Ok(dir) => dir,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut modules = Vec::new();
+        for path in dir.flatten().map(|entry| entry.path()) {
+            if path.extension().and_then(|ext| ext.to_str()) != Some("rhai") {
+                continue;
+            }
+
+            match Self::load(&path) {
+                Ok(loaded) => modules.extend(loaded.into_iter().map(Ok)),
+                Err(e) => modules.push(Err(format!("{}: {}", path.display(), e))),
+            }
+        }
+        modules
+    }
+
+    fn load(path: &Path) -> Result<Vec<Self>, Box<dyn std::error::Error>> {
+        Self::from_source(&fs::read_to_string(path)?)
+    }
+
+    // runs a script, giving the providers it registered
+    fn from_source(source: &str) -> Result<Vec<Self>, Box<dyn std::error::Error>> {
+        let providers = Arc::new(Mutex::new(Vec::new()));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        // stdout isn't visible to anyone anyway
+        engine.on_print(|s| eprintln!("[RHAI] {}", s));
+        register_api(&mut engine);
+
+        let registered = Arc::clone(&providers);
+        engine.register_fn("register_provider", move |provider: Map| {
+            registered.lock().unwrap().push(provider);
+        });
+
+        let ast = engine.compile(source)?;
+        engine.run_ast(&ast)?;
+
+        let script = Arc::new(Script { engine, ast });
+        let providers = std::mem::take(&mut *providers.lock().unwrap());
+        providers
+            .into_iter()
+            .map(|provider| Self::from_provider(provider, &script))
+            .collect()
+    }
+
+    fn from_provider(
+        mut provider: Map,
+        script: &Arc<Script>,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let name = take_string(&mut provider, "name")?.ok_or("provider without a `name`")?;
+        let matches = provider
+            .remove("matches")
+            .and_then(|f| f.try_cast::<FnPtr>())
+            .ok_or_else(|| format!("{}: `matches` has to be a function", name))?;
+
+        Ok(Self {
+            description: take_string(&mut provider, "description")?
+                .unwrap_or_else(|| format!("Rhai provider {}", name)),
+            trigger: take_string(&mut provider, "trigger")?,
+            global: match provider.remove("global") {
+                Some(global) => global.as_bool().map_err(|_| "`global` has to be a bool")?,
+                None => true,
+            },
+            name,
+            matches,
+            script: Arc::clone(script),
+            icon_theme: None,
+        })
+    }
+
+    fn parse_item(&self, item: Dynamic) -> Result<(i64, Match), Box<dyn std::error::Error>> {
+        let mut item = item
+            .try_cast::<Map>()
+            .ok_or("`matches` has to return a list of maps")?;
+
+        let title = take_string(&mut item, "title")?.ok_or("item without a `title`")?;
+        let score = match item.remove("score") {
+            Some(score) => score.as_int().map_err(|_| "`score` has to be an integer")?,
+            None => 0,
+        };
+        let action = match item.remove("action") {
+            Some(action) => Some(
+                action
+                    .try_cast::<Action>()
+                    .ok_or("`action` has to be created by run, copy, open_url or set_query")?,
+            ),
+            None => None,
+        };
+        let icon = take_string(&mut item, "icon")?
+            .and_then(|icon| resolve_icon(&icon, self.icon_theme.as_ref()));
+
+        let suggestion = RhaiSuggestion {
+            module: self.name.clone(),
+            title,
+            label: take_string(&mut item, "label")?,
+            subtitle: take_string(&mut item, "subtitle")?,
+            icon,
+            action,
+        };
+
+        let level = score_level(score.clamp(0, 100) as u8);
+        Ok((score, (level, Arc::new(suggestion))))
+    }
+}
+
+impl SuggestionModule for RhaiModule {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        self.icon_theme = Some(ctx.icon_theme.clone());
+        Ok(())
+    }
+
+    fn trigger(&self) -> Option<&str> {
+        self.trigger.as_deref()
+    }
+
+    fn global(&self) -> bool {
+        self.global
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let items: Array =
+            self.matches
+                .call(&self.script.engine, &self.script.ast, (query.to_string(),))?;
+
+        let mut results = items
+            .into_iter()
+            .map(|item| self.parse_item(item))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if !cancel.is_cancelled() {
+            results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            v.extend(results.into_iter().map(|(_, m)| m));
+        }
+
+        Ok(())
+    }
+}
+
+// the functions scripts get on top of the rhai standard library
+fn register_api(engine: &mut Engine) {
+    engine.register_type_with_name::<Action>("Action");
+    engine.register_fn("run", |cmd: &str| Action::Run(cmd.to_string()));
+    engine.register_fn("copy", |text: &str| Action::Copy(text.to_string()));
+    engine.register_fn("open_url", |url: &str| Action::OpenUrl(url.to_string()));
+    engine.register_fn("set_query", |query: &str| {
+        Action::SetQuery(query.to_string())
+    });
+
+    engine.register_fn("score", |text: &str, query: &str| -> i64 {
+        let query = normalize(query);
+        if normalize(text) == query {
+            return 100;
+        }
+        match SearchKey::new(text).match_level(&query) {
+            MatchLevel::Exact => 100,
+            MatchLevel::Initials => 80,
+            MatchLevel::Contained => 60,
+            MatchLevel::Typo(_) | MatchLevel::Fallback | MatchLevel::NoMatch => 0,
+        }
+    });
+}
+
+// an optional string field of a map given by a script
+fn take_string(map: &mut Map, key: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    match map.remove(key) {
+        Some(value) => value
+            .into_string()
+            .map(Some)
+            .map_err(|_| format!("`{}` has to be a string", key).into()),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(source: &str) -> RhaiModule {
+        let mut providers = RhaiModule::from_source(source).unwrap();
+        assert_eq!(providers.len(), 1);
+        providers.remove(0)
+    }
+
+    // the matches in their debug form, or the error
+    fn run(module: &RhaiModule, query: &str) -> Result<Vec<String>, String> {
+        let mut matches = Vec::new();
+        module
+            .get_matches(query, &CancelToken::default(), &mut matches)
+            .map_err(|e| e.to_string())?;
+        Ok(matches
+            .iter()
+            .map(|(level, sg)| format!("{:?} {:?}", level, sg))
+            .collect())
+    }
+
+    // a provider whose `matches` returns `items`
+    fn returning(items: &str) -> RhaiModule {
+        provider(&format!(
+            "register_provider(#{{ name: \"test\", matches: |query| {} }});",
+            items
+        ))
+    }
+
+    #[test]
+    fn providers() {
+        let providers = RhaiModule::from_source(
+            r#"
+            register_provider(#{
+                name: "greet",
+                description: "Says hello",
+                trigger: "hi",
+                global: false,
+                matches: |query| [],
+            });
+            register_provider(#{ name: "plain", matches: |query| [] });
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(providers.len(), 2);
+        assert_eq!(providers[0].name(), "greet");
+        assert_eq!(providers[0].description(), "Says hello");
+        assert_eq!(providers[0].trigger(), Some("hi"));
+        assert!(!providers[0].global());
+
+        assert_eq!(providers[1].description(), "Rhai provider plain");
+        assert_eq!(providers[1].trigger(), None);
+        assert!(providers[1].global());
+    }
+
+    #[test]
+    fn broken_providers() {
+        let error = |source: &str| RhaiModule::from_source(source).err().unwrap().to_string();
+
+        assert_eq!(
+            error("register_provider(#{ matches: |q| [] });"),
+            "provider without a `name`"
+        );
+        assert_eq!(
+            error(r#"register_provider(#{ name: "x", matches: 1 });"#),
+            "x: `matches` has to be a function"
+        );
+        assert_eq!(
+            error(r#"register_provider(#{ name: "x", global: "no", matches: |q| [] });"#),
+            "`global` has to be a bool"
+        );
+        // doesn't compile
+        assert!(RhaiModule::from_source("register_provider(").is_err());
+    }
+
+    #[test]
+    fn items() {
+        let module = provider(
+            r#"
+            register_provider(#{
+                name: "greet",
+                matches: |query| [
+                    #{ title: "low" },
+                    #{ title: `Hello ${query}`, subtitle: "greeting", label: "say",
+                       score: 100, action: copy(`Hello ${query}`) },
+                    #{ title: "ranked", score: score("Firefox", query), action: run("firefox") },
+                ],
+            });
+            "#,
+        );
+
+        let matches = run(&module, "fire").unwrap();
+        assert_eq!(matches.len(), 3);
+        assert!(matches[0].starts_with("Exact"));
+        assert!(matches[0].contains(r#"title: "Hello fire""#));
+        assert!(matches[0].contains(r#"label: Some("say")"#));
+        assert!(matches[0].contains(r#"subtitle: Some("greeting")"#));
+        assert!(matches[0].contains(r#"Copy("Hello fire")"#));
+        assert!(matches[1].starts_with("Contained"));
+        assert!(matches[1].contains(r#"Run("firefox")"#));
+        assert!(matches[2].contains(r#"title: "low""#));
+        assert!(matches[2].contains("action: None"));
+    }
+
+    #[test]
+    fn wrong_shapes() {
+        assert!(run(&returning(r#""nope""#), "x").is_err());
+        assert_eq!(
+            run(&returning(r#"["nope"]"#), "x"),
+            Err(String::from("`matches` has to return a list of maps"))
+        );
+        assert_eq!(
+            run(&returning("[#{ subtitle: \"no title\" }]"), "x"),
+            Err(String::from("item without a `title`"))
+        );
+        assert_eq!(
+            run(&returning("[#{ title: 1 }]"), "x"),
+            Err(String::from("`title` has to be a string"))
+        );
+        assert_eq!(
+            run(&returning("[#{ title: \"x\", score: \"high\" }]"), "x"),
+            Err(String::from("`score` has to be an integer"))
+        );
+        assert_eq!(
+            run(&returning("[#{ title: \"x\", action: \"firefox\" }]"), "x"),
+            Err(String::from(
+                "`action` has to be created by run, copy, open_url or set_query"
+            ))
+        );
+    }
+
+    #[test]
+    fn endless_scripts_are_stopped() {
+        let module = returning("{ loop {} }");
+        assert!(run(&module, "x").is_err());
+    }
+}
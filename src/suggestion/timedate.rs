@@ -3,72 +3,135 @@ use chrono_tz::Tz;
 
 use iced::widget::{column, horizontal_space, row, text, vertical_space};
 use iced::Length;
+use serde::Deserialize;
 
-use std::cell::Cell;
 use std::fmt::Display;
+use std::sync::Arc;
 
+use super::matching::normalize;
 use super::*;
 
-fn get_timezone(query: &str, keyword: &str) -> Option<Tz> {
-    let mut tz_string = query.to_string();
-
-    let keyword_idx = query.find(keyword).unwrap();
-    tz_string.replace_range(keyword_idx..keyword_idx + keyword.len(), "");
-    let tz_string = tz_string.trim();
-
-    if tz_string.is_empty() {
-        return None;
-    }
-
-    let tz_string: String = tz_string
-        .split(' ')
-        .filter(|word| !word.is_empty())
-        .enumerate()
-        .map(|(i, word)| {
-            format!(
-                "{}{}{}",
-                if i != 0 { "_" } else { "" },
-                word.get(0..1).unwrap().to_uppercase(),
-                word.get(1..).unwrap()
-            )
-        })
-        .collect();
+mod calendar;
+mod conversion;
+mod dates;
+mod places;
+mod timestamp;
+mod worldclock;
+mod zone;
+
+use calendar::CalendarSuggestion;
+use conversion::ConversionSuggestion;
+use dates::DateInfoSuggestion;
+use timestamp::TimestampSuggestion;
+use worldclock::{Clock, ClockConfig, WorldClockSuggestion};
+
+// the keyword has to be a word of its own, so "runtime" or "datetime-tool" don't count
+fn has_keyword(query: &str, keyword: &str) -> bool {
+    query.split_whitespace().any(|word| word == keyword)
+}
+
+/// A zone a query asked for, named after the place it was found by
+#[derive(Debug)]
+struct Location {
+    tz: Tz,
+
+    // left out when the zone is named after the place anyway, like Asia/Tokyo for "tokyo"
+    label: Option<String>,
+}
 
-    if let Ok(tz) = tz_string.parse::<Tz>() {
-        return Some(tz);
-    }
-
-    // this shouldn't be _too_ expensive since .parse::<Tz>() uses a hashmap under the hood
-    let prefixes = [
-        "Africa",
-        "Australia",
-        "Asia",
-        "Europe",
-        "America",
-        "Pacific",
-    ];
-    for prefix in prefixes {
-        if let Ok(tz) = format!("{prefix}/{tz_string}").parse::<Tz>() {
-            return Some(tz);
+impl Location {
+    // the part of the country only matters when there are several to pick from
+    fn from_place(place: &places::Place, alone: bool) -> Self {
+        let label = if alone {
+            place.name.clone()
+        } else {
+            place.label()
+        };
+        let city = place.tz.name().rsplit('/').next().unwrap_or_default();
+        Self {
+            tz: place.tz,
+            label: (label.replace(' ', "_") != city).then_some(label),
         }
     }
 
-    None
+    fn name(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{} [{}]", label, self.tz.name()),
+            None => self.tz.name().to_string(),
+        }
+    }
+}
+
+// the current time at `location` or the local one, as it's copied
+fn now_in(location: Option<&Location>, format: &str) -> String {
+    match location {
+        Some(location) => Local::now()
+            .with_timezone(&location.tz)
+            .format(format)
+            .to_string(),
+        None => Local::now().format(format).to_string(),
+    }
+}
+
+// the places a query asks for the time or date in, along with how well they matched. Places
+// that can't be found fall back to the local time zone, countries spanning several zones give
+// several candidates.
+fn get_locations(query: &str, keyword: &str) -> Vec<(MatchLevel, Option<Location>)> {
+    if !has_keyword(query, keyword) {
+        return Vec::new();
+    }
+
+    let words: Vec<&str> = query
+        .split_whitespace()
+        .filter(|word| *word != keyword)
+        .collect();
+    if words.is_empty() {
+        return vec![(MatchLevel::Exact, None)];
+    }
+
+    if let Some(tz) = places::zone_by_name(&words) {
+        return vec![(MatchLevel::Exact, Some(Location { tz, label: None }))];
+    }
+
+    let found = places::lookup(&words);
+    if found.is_empty() {
+        return vec![(MatchLevel::Contained, None)];
+    }
+    let alone = found.len() == 1;
+    found
+        .into_iter()
+        .map(|(level, place)| (level, Some(Location::from_place(place, alone))))
+        .collect()
 }
 
 /// Display the time
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct TimeSuggestion {
-    time_zone: Cell<Option<Tz>>,
+    location: Option<Location>,
+    level: MatchLevel,
+}
+
+impl TimeSuggestion {
+    // `query` is expected to be normalized
+    pub fn from_query(query: &str) -> Vec<Self> {
+        get_locations(query, "time")
+            .into_iter()
+            .map(|(level, location)| Self { location, level })
+            .collect()
+    }
 }
 
 impl Suggestion for TimeSuggestion {
-    fn view(&self) -> Element<LanchMessage> {
+    fn view(&self) -> Element<'_, LanchMessage> {
         let now = Local::now();
 
-        let txt = if let Some(tz) = self.time_zone.get() {
-            let tz_now = now.with_timezone(&tz);
-            text(format!("{}: {}", tz.name(), tz_now.format("%H:%M:%S")))
+        let txt = if let Some(location) = &self.location {
+            let tz_now = now.with_timezone(&location.tz);
+            text(format!(
+                "{}: {}",
+                location.name(),
+                tz_now.format("%H:%M:%S")
+            ))
         } else {
             text(now.format("Local time: %H:%M:%S"))
         };
@@ -86,22 +149,18 @@ impl Suggestion for TimeSuggestion {
     }
 
     fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
-        Ok(None)
-    } // TODO: copy to clipboard
-
-    fn matches(&self, query: &str) -> MatchLevel {
-        if query.contains("time") {
-            let tz = get_timezone(query, "time");
-            self.time_zone.set(tz);
-
-            return if tz.is_some() || query == "time" {
-                MatchLevel::Exact
-            } else {
-                MatchLevel::Contained
-            };
-        }
+        Ok(Some(LanchMessage::CopyToClipboard(now_in(
+            self.location.as_ref(),
+            "%H:%M:%S",
+        ))))
+    }
 
-        MatchLevel::NoMatch
+    fn matches(&self, _query: &str) -> MatchLevel {
+        self.level
+    }
+
+    fn live(&self) -> bool {
+        true
     }
 }
 
@@ -112,20 +171,31 @@ impl Display for TimeSuggestion {
 }
 
 /// Display the Date
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct DateSuggestion {
-    time_zone: Cell<Option<Tz>>,
+    location: Option<Location>,
+    level: MatchLevel,
+}
+
+impl DateSuggestion {
+    // `query` is expected to be normalized
+    pub fn from_query(query: &str) -> Vec<Self> {
+        get_locations(query, "date")
+            .into_iter()
+            .map(|(level, location)| Self { location, level })
+            .collect()
+    }
 }
 
 impl Suggestion for DateSuggestion {
-    fn view(&self) -> Element<LanchMessage> {
+    fn view(&self) -> Element<'_, LanchMessage> {
         let now = Local::now();
 
-        let txt = if let Some(tz) = self.time_zone.get() {
-            let tz_now = now.with_timezone(&tz);
+        let txt = if let Some(location) = &self.location {
+            let tz_now = now.with_timezone(&location.tz);
             text(format!(
                 "Date [{}]: {}",
-                tz.name(),
+                location.name(),
                 tz_now.format("%d of %B %Y")
             ))
         } else {
@@ -145,22 +215,14 @@ impl Suggestion for DateSuggestion {
     }
 
     fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
-        Ok(None)
-    } // TODO: copy to clipboard
-
-    fn matches(&self, query: &str) -> MatchLevel {
-        if query.contains("date") {
-            let tz = get_timezone(query, "date");
-            self.time_zone.set(tz);
-
-            return if tz.is_some() || query == "date" {
-                MatchLevel::Exact
-            } else {
-                MatchLevel::Contained
-            };
-        }
+        Ok(Some(LanchMessage::CopyToClipboard(now_in(
+            self.location.as_ref(),
+            dates::ISO_FORMAT,
+        ))))
+    }
 
-        MatchLevel::NoMatch
+    fn matches(&self, _query: &str) -> MatchLevel {
+        self.level
     }
 }
 
@@ -170,33 +232,147 @@ impl Display for DateSuggestion {
     }
 }
 
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct TimeDateConfig {
+    // shown along with the local time when asking for "time"
+    clocks: Vec<ClockConfig>,
+}
+
+#[derive(Default)]
 pub struct TimeDateModule {
-    time: Rc<TimeSuggestion>,
-    date: Rc<DateSuggestion>,
+    clocks: Arc<Vec<Clock>>,
 }
 
-impl TimeDateModule {
-    pub fn new() -> Self {
-        Self {
-            time: Rc::new(TimeSuggestion::default()),
-            date: Rc::new(DateSuggestion::default()),
+impl SuggestionModule for TimeDateModule {
+    fn name(&self) -> &str {
+        "timedate"
+    }
+
+    fn description(&self) -> &str {
+        "Current time and date in any time zone, conversions like \"15:00 london in tokyo\", date \
+         arithmetic, calendars and timestamp conversions"
+    }
+
+    // no trigger, "time" and "date" can stand anywhere in a query ("tokyo time", "date +90d")
+    // and the module needs to see them, a trigger only works as a prefix and gets stripped.
+    // Keywords are matched as whole words, so "runtime" doesn't bring up any clocks.
+    fn trigger(&self) -> Option<&str> {
+        None
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        let config: TimeDateConfig = toml::Value::Table(ctx.settings.clone()).try_into()?;
+        self.clocks = Arc::new(
+            config
+                .clocks
+                .into_iter()
+                .map(Clock::from_config)
+                .collect::<Result<_, _>>()?,
+        );
+        Ok(())
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        // timestamps are taken as they are, normalizing would lose RFC 2822's capitals
+        for timestamp in TimestampSuggestion::from_query(query.trim(), &self.clocks) {
+            v.push((MatchLevel::Exact, Arc::new(timestamp)));
         }
+
+        let query = &normalize(query);
+
+        if let Some(conversion) = ConversionSuggestion::from_query(query) {
+            v.push((MatchLevel::Exact, Arc::new(conversion)));
+        }
+
+        for time in TimeSuggestion::from_query(query) {
+            // "time" on its own gets the world clock, which includes the local time
+            if time.location.is_none() && time.level == MatchLevel::Exact && !self.clocks.is_empty()
+            {
+                let clock = WorldClockSuggestion::new(Arc::clone(&self.clocks));
+                v.push((MatchLevel::Exact, Arc::new(clock)));
+                continue;
+            }
+            v.push((time.level, Arc::new(time)));
+        }
+        if let Some(calendar) = CalendarSuggestion::from_query(query) {
+            v.push((MatchLevel::Exact, Arc::new(calendar)));
+        }
+
+        // "date +90d" isn't asking for the date in some place called "+90d"
+        if let Some(info) = DateInfoSuggestion::from_query(query) {
+            v.push((MatchLevel::Exact, Arc::new(info)));
+            return Ok(());
+        }
+        for date in DateSuggestion::from_query(query) {
+            v.push((date.level, Arc::new(date)));
+        }
+
+        Ok(())
     }
 }
 
-impl SuggestionModule for TimeDateModule {
-    fn get_matches(&mut self, query: &str, v: &mut VecDeque<Rc<dyn Suggestion>>) {
-        // Only exact matches for now, could be improved by trying to guess the locations etc.
-        match self.time.matches(query) {
-            MatchLevel::Exact => v.push_front(Rc::clone(&self.time) as Rc<dyn Suggestion>),
-            MatchLevel::Contained => v.push_back(Rc::clone(&self.time) as Rc<dyn Suggestion>),
-            MatchLevel::NoMatch => {}
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time_in(query: &str) -> Vec<(MatchLevel, Option<&'static str>)> {
+        TimeSuggestion::from_query(&normalize(query))
+            .into_iter()
+            .map(|time| (time.level, time.location.map(|l| l.tz.name())))
+            .collect()
+    }
+
+    #[test]
+    fn places_with_diacritics() {
+        assert_eq!(
+            time_in("time são paulo"),
+            [(MatchLevel::Exact, Some("America/Sao_Paulo"))]
+        );
+        assert_eq!(
+            time_in("Zürich time"),
+            [(MatchLevel::Exact, Some("Europe/Zurich"))]
+        );
+        assert_eq!(
+            time_in("time ZÜRICH"),
+            [(MatchLevel::Exact, Some("Europe/Zurich"))]
+        );
+    }
+
+    #[test]
+    fn unknown_places_fall_back_to_local_time() {
+        assert_eq!(time_in("time é"), [(MatchLevel::Contained, None)]);
+        assert_eq!(time_in("time 東京"), [(MatchLevel::Contained, None)]);
+        assert_eq!(time_in("time"), [(MatchLevel::Exact, None)]);
+    }
 
-        match self.date.matches(query) {
-            MatchLevel::Exact => v.push_front(Rc::clone(&self.date) as Rc<dyn Suggestion>),
-            MatchLevel::Contained => v.push_back(Rc::clone(&self.date) as Rc<dyn Suggestion>),
-            MatchLevel::NoMatch => {}
+    #[test]
+    fn non_ascii_queries_dont_panic() {
+        let module = TimeDateModule::default();
+        for query in [
+            "time é",
+            "time 東京",
+            "date 東京",
+            "é",
+            "東京",
+            "15:00 東京 in zürich",
+            "15:00 são paulo in é",
+            "cal é",
+            "date +é",
+            "ü+2",
+            "utc+é",
+            "@é",
+            "2026-10-19T14:03:00é",
+        ] {
+            let mut matches = Vec::new();
+            module
+                .get_matches(query, &CancelToken::default(), &mut matches)
+                .unwrap();
         }
     }
 }
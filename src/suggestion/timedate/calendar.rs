@@ -0,0 +1,165 @@
+use chrono::{Datelike, Duration, Month, NaiveDate};
+use iced::alignment::Horizontal;
+use iced::widget::{column, horizontal_space, row, text, vertical_space, Row};
+use iced::{theme, Color, Length};
+
+use std::fmt::Display;
+
+use super::dates::today;
+use super::*;
+
+const WEEKDAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+// wide enough for a two digit day in any reasonable font size
+const CELL_WIDTH: f32 = 34f32;
+
+/// A month as a grid of weeks, for "cal", "cal march" or "calendar 2027-03"
+#[derive(Debug)]
+pub struct CalendarSuggestion {
+    // the first day of the month
+    month: NaiveDate,
+    today: NaiveDate,
+}
+
+impl CalendarSuggestion {
+    // `query` is expected to be normalized
+    pub fn from_query(query: &str) -> Option<Self> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let today = today();
+
+        let (year, month) = match words.as_slice() {
+            ["cal" | "calendar"] => (today.year(), today.month()),
+            ["cal" | "calendar", month] => match month.parse::<Month>() {
+                Ok(month) => (today.year(), month.number_from_month()),
+                Err(_) => {
+                    let (year, month) = month.split_once('-')?;
+                    (year.parse().ok()?, month.parse().ok()?)
+                }
+            },
+            ["cal" | "calendar", month, year] => (
+                year.parse().ok()?,
+                month.parse::<Month>().ok()?.number_from_month(),
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            month: NaiveDate::from_ymd_opt(year, month, 1)?,
+            today,
+        })
+    }
+}
+
+// a number right aligned in its column
+fn cell<'a>(content: String) -> iced::widget::Text<'a, iced::Renderer> {
+    text(content)
+        .width(Length::Fixed(CELL_WIDTH))
+        .horizontal_alignment(Horizontal::Right)
+}
+
+impl Suggestion for CalendarSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let grey = theme::Text::Color(Color::from([0.6, 0.6, 0.6]));
+
+        let header = WEEKDAYS.iter().fold(
+            Row::new().push(cell(String::from("Wk")).style(grey)),
+            |header, day| header.push(cell(day.to_string()).style(grey)),
+        );
+        let mut grid = column![text(self.month.format("%B %Y")), header].spacing(2);
+
+        // whole weeks from the monday on or before the first until the month is over, iterating
+        // stops at the end of chrono's range so far off months can't loop forever
+        let first = self
+            .month
+            .checked_sub_signed(Duration::days(
+                self.month.weekday().num_days_from_monday().into(),
+            ))
+            .unwrap_or(self.month);
+        let days: Vec<NaiveDate> = first
+            .iter_days()
+            .take_while(|day| *day < self.month || day.month() == self.month.month())
+            .collect();
+
+        for days in days.chunks(7) {
+            let mut week = Row::new().push(cell(days[0].iso_week().week().to_string()).style(grey));
+            for day in days {
+                let number = if day.month() == self.month.month() {
+                    day.day().to_string()
+                } else {
+                    String::new()
+                };
+                week = if *day == self.today {
+                    week.push(cell(number).style(theme::Text::Color(Color::from_rgb8(51, 89, 218))))
+                } else {
+                    week.push(cell(number))
+                };
+            }
+            grid = grid.push(week);
+        }
+
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                grid,
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        Ok(Some(LanchMessage::CopyToClipboard(
+            self.month.format("%Y-%m").to_string(),
+        )))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for CalendarSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Calendar")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn months() {
+        let month = |query| CalendarSuggestion::from_query(query).map(|cal| cal.month);
+        let today = today();
+
+        assert_eq!(month("cal"), today.with_day(1));
+        assert_eq!(
+            month("cal march"),
+            NaiveDate::from_ymd_opt(today.year(), 3, 1)
+        );
+        assert_eq!(
+            month("calendar feb 2028"),
+            NaiveDate::from_ymd_opt(2028, 2, 1)
+        );
+        assert_eq!(month("cal 2027-03"), NaiveDate::from_ymd_opt(2027, 3, 1));
+        assert_eq!(month("cal 2027-13"), None);
+        assert_eq!(month("cal tokyo"), None);
+    }
+
+    #[test]
+    fn months_at_the_end_of_the_range() {
+        // used to loop forever as the days after the last one can't be represented
+        let max = NaiveDate::MAX;
+        let query = format!("cal {}-{}", max.year(), max.month());
+        let last = CalendarSuggestion::from_query(&query).unwrap();
+        let _ = last.view();
+
+        let min = NaiveDate::MIN;
+        let query = format!("cal {} {}", min.format("%B"), min.year());
+        let first = CalendarSuggestion::from_query(&normalize(&query)).unwrap();
+        let _ = first.view();
+    }
+}
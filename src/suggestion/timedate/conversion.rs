@@ -0,0 +1,210 @@
+use chrono::{NaiveTime, Utc};
+use iced::widget::{column, horizontal_space, row, text, vertical_space};
+use iced::{theme, Color, Length};
+
+use std::fmt::Display;
+
+use super::zone::Zone;
+use super::*;
+
+// words between the source and the target zone
+const SEPARATORS: [&str; 3] = ["in", "to", "->"];
+
+/// "15:00", "9am", "9:30 pm" or "noon" at the start of `words`, along with whether it was given
+/// in 12 hour format and how many words it took up
+fn parse_time(words: &[&str]) -> Option<(NaiveTime, bool, usize)> {
+    let first = *words.first()?;
+    match first {
+        "noon" => return Some((NaiveTime::from_hms_opt(12, 0, 0)?, false, 1)),
+        "midnight" => return Some((NaiveTime::from_hms_opt(0, 0, 0)?, false, 1)),
+        _ => {}
+    }
+
+    let (clock, suffix, used) = match (first.strip_suffix("am"), first.strip_suffix("pm")) {
+        (Some(clock), _) => (clock, Some("am"), 1),
+        (_, Some(clock)) => (clock, Some("pm"), 1),
+        _ => match words.get(1).copied() {
+            Some(suffix @ ("am" | "pm")) => (first, Some(suffix), 2),
+            _ => (first, None, 1),
+        },
+    };
+
+    let (hour, minute) = match clock.split_once([':', '.']) {
+        Some((hour, minute)) if minute.len() == 2 => (hour, minute),
+        Some(_) => return None,
+        // a bare number is only a time with am/pm, "12 in to cm" is something else
+        None if suffix.is_some() => (clock, "0"),
+        None => return None,
+    };
+    let hour: u32 = hour.parse().ok()?;
+    let minute: u32 = minute.parse().ok()?;
+
+    let hour = match suffix {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some("am") => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None => hour,
+    };
+    Some((
+        NaiveTime::from_hms_opt(hour, minute, 0)?,
+        suffix.is_some(),
+        used,
+    ))
+}
+
+/// Converts a time between zones, like "15:00 london in new york" or "9am pst to cet"
+#[derive(Debug)]
+pub struct ConversionSuggestion {
+    from: String,
+    to: String,
+
+    // the converted time, what gets copied
+    value: String,
+
+    // day rollovers and daylight saving time
+    notes: Vec<String>,
+}
+
+impl ConversionSuggestion {
+    // `query` is expected to be normalized
+    pub fn from_query(query: &str) -> Option<Self> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let (time, twelve_hour, used) = parse_time(&words)?;
+        let rest = &words[used..];
+
+        // the source zone is optional, "15:00 in tokyo" converts from the local time
+        let (source, target) = rest
+            .iter()
+            .enumerate()
+            .filter(|(_, word)| SEPARATORS.contains(word))
+            .find_map(|(i, _)| {
+                let source = match i {
+                    0 => Zone::Local,
+                    _ => Zone::parse(&rest[..i])?,
+                };
+                Some((source, Zone::parse(&rest[i + 1..])?))
+            })?;
+
+        let date = source.wall_clock(Utc::now()).date();
+        let instant = source.localize(date.and_time(time))?;
+        let converted = target.wall_clock(instant);
+
+        let format = if twelve_hour { "%-I:%M %p" } else { "%H:%M" };
+        let describe = |zone: &Zone, time: NaiveTime| {
+            let name = zone.name();
+            let abbreviation = zone.abbreviation(instant);
+            if name == abbreviation {
+                format!("{} {}", time.format(format), name)
+            } else {
+                format!("{} {} ({})", time.format(format), name, abbreviation)
+            }
+        };
+
+        let mut notes = Vec::new();
+        match (converted.date() - date).num_days() {
+            0 => {}
+            1 => notes.push(format!("next day, {}", converted.format("%a %-d %b"))),
+            -1 => notes.push(format!("previous day, {}", converted.format("%a %-d %b"))),
+            days => notes.push(format!(
+                "{:+} days, {}",
+                days,
+                converted.format("%a %-d %b")
+            )),
+        }
+        notes.extend(source.dst_note(instant));
+        notes.extend(target.dst_note(instant));
+
+        Some(Self {
+            from: describe(&source, time),
+            to: describe(&target, converted.time()),
+            value: converted.time().format(format).to_string(),
+            notes,
+        })
+    }
+}
+
+impl Suggestion for ConversionSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let mut lines = column![text(format!("{} = {}", self.from, self.to))];
+        if !self.notes.is_empty() {
+            lines = lines.push(
+                text(self.notes.join(" · "))
+                    .size(14)
+                    .style(theme::Text::Color(Color::from([0.6, 0.6, 0.6]))),
+            );
+        }
+
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                lines,
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        Ok(Some(LanchMessage::CopyToClipboard(self.value.clone())))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for ConversionSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Time zone conversion")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(words: &str) -> Option<(String, bool, usize)> {
+        let words: Vec<&str> = words.split_whitespace().collect();
+        parse_time(&words)
+            .map(|(time, twelve_hour, used)| (time.format("%H:%M").to_string(), twelve_hour, used))
+    }
+
+    #[test]
+    fn times() {
+        assert_eq!(
+            time("15:00 london"),
+            Some((String::from("15:00"), false, 1))
+        );
+        assert_eq!(time("9am pst"), Some((String::from("09:00"), true, 1)));
+        assert_eq!(time("9 pm"), Some((String::from("21:00"), true, 2)));
+        assert_eq!(time("12am"), Some((String::from("00:00"), true, 1)));
+        assert_eq!(time("12:30pm"), Some((String::from("12:30"), true, 1)));
+        assert_eq!(time("7.45"), Some((String::from("07:45"), false, 1)));
+        assert_eq!(time("noon"), Some((String::from("12:00"), false, 1)));
+        assert_eq!(time("midnight"), Some((String::from("00:00"), false, 1)));
+    }
+
+    #[test]
+    fn not_times() {
+        // a bare number is more likely a length or an amount
+        assert_eq!(time("12 in to cm"), None);
+        assert_eq!(time("13pm"), None);
+        assert_eq!(time("25:00"), None);
+        assert_eq!(time("9:5"), None);
+        assert_eq!(time("london"), None);
+    }
+
+    #[test]
+    fn conversions() {
+        let conversion = ConversionSuggestion::from_query("15:00 utc to utc+5:30").unwrap();
+        assert_eq!(conversion.value, "20:30");
+
+        let conversion = ConversionSuggestion::from_query("11pm utc+0 in utc+2").unwrap();
+        assert_eq!(conversion.value, "1:00 AM");
+        assert!(conversion.notes[0].starts_with("next day"));
+
+        assert!(ConversionSuggestion::from_query("15:00 london").is_none());
+    }
+}
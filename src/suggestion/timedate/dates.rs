@@ -0,0 +1,398 @@
+use chrono::{Datelike, Duration, Local, Month, Months, NaiveDate, Weekday};
+use iced::widget::{column, horizontal_space, row, text, vertical_space};
+use iced::{theme, Color, Length};
+
+use std::fmt::Display;
+
+use super::*;
+
+/// How dates are written out in the suggestion list
+pub const LONG_FORMAT: &str = "%A %-d %B %Y";
+
+/// How dates are copied
+pub const ISO_FORMAT: &str = "%Y-%m-%d";
+
+/// The local date
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// "24th" or "1st" are days as well
+fn parse_day(s: &str) -> Option<u32> {
+    let digits = s
+        .trim_end_matches(',')
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    digits.parse().ok()
+}
+
+/// A date filling all of `words`: "2026-12-24", "today", "24 december", "dec 24 2027" and the
+/// like. Without a year it's the next time the day comes around, or the last time if `forward`
+/// is false.
+pub fn parse_date(words: &[&str], today: NaiveDate, forward: bool) -> Option<NaiveDate> {
+    match words {
+        ["today"] => return Some(today),
+        ["tomorrow"] => return today.succ_opt(),
+        ["yesterday"] => return today.pred_opt(),
+        [iso] => return NaiveDate::parse_from_str(iso, ISO_FORMAT).ok(),
+        _ => {}
+    }
+
+    let (day, month, year) = match words {
+        [a, b] | [a, b, _] => match (parse_day(a), b.parse::<Month>(), a.parse::<Month>()) {
+            (Some(day), Ok(month), _) => (day, month, words.get(2)),
+            (None, _, Ok(month)) => (parse_day(b)?, month, words.get(2)),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let month = month.number_from_month();
+    if let Some(year) = year {
+        return NaiveDate::from_ymd_opt(year.parse().ok()?, month, day);
+    }
+
+    let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+    match this_year {
+        Some(date) if forward && date < today => {
+            NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+        }
+        Some(date) if !forward && date > today => {
+            NaiveDate::from_ymd_opt(today.year() - 1, month, day)
+        }
+        // the 29th of February is only a date every few years
+        None => (1..8)
+            .map(|n| {
+                if forward {
+                    today.year() + n
+                } else {
+                    today.year() - n
+                }
+            })
+            .find_map(|year| NaiveDate::from_ymd_opt(year, month, day)),
+        date => date,
+    }
+}
+
+/// Moves `date` by an offset like "+90d", "-2w" or "+1y-3d"
+fn apply_offset(date: NaiveDate, offset: &str) -> Option<NaiveDate> {
+    let mut date = date;
+    let mut rest = offset;
+
+    while !rest.is_empty() {
+        let negative = match rest.chars().next()? {
+            '+' => false,
+            '-' | '−' => true,
+            _ => return None,
+        };
+        rest = &rest[rest.chars().next()?.len_utf8()..];
+
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: u32 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let letters = rest
+            .find(|c: char| !c.is_alphabetic())
+            .unwrap_or(rest.len());
+        let unit = &rest[..letters];
+        rest = &rest[letters..];
+
+        let months = match unit {
+            "d" | "day" | "days" => None,
+            "w" | "wk" | "week" | "weeks" => None,
+            "m" | "mo" | "month" | "months" => Some(amount),
+            "y" | "yr" | "year" | "years" => Some(amount.checked_mul(12)?),
+            _ => return None,
+        };
+        date = match months {
+            Some(months) if negative => date.checked_sub_months(Months::new(months))?,
+            Some(months) => date.checked_add_months(Months::new(months))?,
+            None => {
+                let days = if unit.starts_with('w') {
+                    i64::from(amount) * 7
+                } else {
+                    i64::from(amount)
+                };
+                let days = if negative { -days } else { days };
+                date.checked_add_signed(Duration::days(days))?
+            }
+        };
+    }
+
+    Some(date)
+}
+
+/// "today", "in 3 days" or "2 days ago"
+pub fn relative_days(days: i64) -> String {
+    match days {
+        0 => String::from("today"),
+        1 => String::from("tomorrow"),
+        -1 => String::from("yesterday"),
+        days if days > 0 => format!("in {} days", days),
+        days => format!("{} days ago", -days),
+    }
+}
+
+// "9 weeks and 3 days", nothing when it's less than a week anyway
+fn weeks_and_days(days: i64) -> Option<String> {
+    let days = days.abs();
+    let plural = |n: i64, word: &str| match n {
+        1 => format!("1 {}", word),
+        n => format!("{} {}s", n, word),
+    };
+    match (days / 7, days % 7) {
+        (0, _) => None,
+        (weeks, 0) => Some(plural(weeks, "week")),
+        (weeks, rest) => Some(format!(
+            "{} and {}",
+            plural(weeks, "week"),
+            plural(rest, "day")
+        )),
+    }
+}
+
+/// A date worked out from a query: the result of date arithmetic, the days until some date, the
+/// day of the week of a date or the days of a calendar week
+#[derive(Debug)]
+pub struct DateInfoSuggestion {
+    kind: &'static str,
+    line: String,
+    notes: Vec<String>,
+
+    // what gets copied, an ISO 8601 date or week
+    value: String,
+}
+
+impl DateInfoSuggestion {
+    // `query` is expected to be normalized
+    pub fn from_query(query: &str) -> Option<Self> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let today = today();
+
+        Self::arithmetic(&words, today)
+            .or_else(|| Self::day_count(&words, today))
+            .or_else(|| Self::weekday(&words, today))
+            .or_else(|| Self::week(&words, today))
+    }
+
+    // "date +90d" or "date 2026-12-24 -2w"
+    fn arithmetic(words: &[&str], today: NaiveDate) -> Option<Self> {
+        let rest = words.strip_prefix(&["date"])?;
+        let split = rest
+            .iter()
+            .position(|word| word.starts_with(['+', '-', '−']))?;
+        let base = match split {
+            0 => today,
+            _ => parse_date(&rest[..split], today, true)?,
+        };
+        let date = apply_offset(base, &rest[split..].concat())?;
+
+        let mut notes = Vec::new();
+        if base != today {
+            notes.push(format!(
+                "{} from {}",
+                rest[split..].concat(),
+                base.format(ISO_FORMAT)
+            ));
+        }
+        notes.push(relative_days((date - today).num_days()));
+        notes.push(format!("week {}", date.iso_week().week()));
+
+        Some(Self {
+            kind: "Date arithmetic",
+            line: date.format(LONG_FORMAT).to_string(),
+            notes,
+            value: date.format(ISO_FORMAT).to_string(),
+        })
+    }
+
+    // "days until 2026-12-24" or "days since 1 january"
+    fn day_count(words: &[&str], today: NaiveDate) -> Option<Self> {
+        let (forward, rest) = match words {
+            ["days", "until" | "till" | "to", rest @ ..] => (true, rest),
+            ["days", "since" | "from", rest @ ..] => (false, rest),
+            _ => return None,
+        };
+        let date = parse_date(rest, today, forward)?;
+        let days = (date - today).num_days();
+
+        let line = match days {
+            0 => format!("{} is today", date.format(LONG_FORMAT)),
+            1 | -1 => format!("{} is {}", date.format(LONG_FORMAT), relative_days(days)),
+            days if days > 0 => format!("{} days until {}", days, date.format(LONG_FORMAT)),
+            days => format!("{} days since {}", -days, date.format(LONG_FORMAT)),
+        };
+
+        Some(Self {
+            kind: "Day count",
+            line,
+            notes: weeks_and_days(days).into_iter().collect(),
+            value: date.format(ISO_FORMAT).to_string(),
+        })
+    }
+
+    // "what day is 2027-03-01"
+    fn weekday(words: &[&str], today: NaiveDate) -> Option<Self> {
+        let (forward, rest) = match words {
+            ["what" | "which", "day", "was", rest @ ..] => (false, rest),
+            ["what" | "which", "day", "is", rest @ ..] => (true, rest),
+            ["what" | "which", "day", "will", "be", rest @ ..] => (true, rest),
+            ["what" | "which", "day", rest @ ..] => (true, rest),
+            _ => return None,
+        };
+        let date = parse_date(rest, today, forward)?;
+
+        Some(Self {
+            kind: "Day of the week",
+            line: date.format(LONG_FORMAT).to_string(),
+            notes: vec![
+                relative_days((date - today).num_days()),
+                format!("week {}", date.iso_week().week()),
+            ],
+            value: date.format(ISO_FORMAT).to_string(),
+        })
+    }
+
+    // "week", "week 42", "week 42 2027" or "week 2026-12-24"
+    fn week(words: &[&str], today: NaiveDate) -> Option<Self> {
+        let rest = words.strip_prefix(&["week"])?;
+        let (year, week) = match rest {
+            [] => (today.iso_week().year(), today.iso_week().week()),
+            [week] if week.len() <= 2 => (today.iso_week().year(), week.parse().ok()?),
+            [week, year] if week.len() <= 2 => (year.parse().ok()?, week.parse().ok()?),
+            _ => {
+                let date = parse_date(rest, today, true)?.iso_week();
+                (date.year(), date.week())
+            }
+        };
+
+        let monday = NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)?;
+        let sunday = NaiveDate::from_isoywd_opt(year, week, Weekday::Sun)?;
+        let this_monday = today - Duration::days(today.weekday().num_days_from_monday().into());
+        let weeks = (monday - this_monday).num_weeks();
+        let relative = match weeks {
+            0 => String::from("this week"),
+            1 => String::from("next week"),
+            -1 => String::from("last week"),
+            weeks if weeks > 0 => format!("in {} weeks", weeks),
+            weeks => format!("{} weeks ago", -weeks),
+        };
+
+        Some(Self {
+            kind: "Calendar week",
+            line: format!(
+                "Week {} of {}: {} to {}",
+                week,
+                year,
+                monday.format("%a %-d %b"),
+                sunday.format("%a %-d %b %Y")
+            ),
+            notes: vec![relative],
+            value: format!("{}-W{:02}", year, week),
+        })
+    }
+}
+
+impl Suggestion for DateInfoSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let mut lines = column![text(&self.line)];
+        if !self.notes.is_empty() {
+            lines = lines.push(
+                text(self.notes.join(" · "))
+                    .size(14)
+                    .style(theme::Text::Color(Color::from([0.6, 0.6, 0.6]))),
+            );
+        }
+
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                lines,
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        Ok(Some(LanchMessage::CopyToClipboard(self.value.clone())))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for DateInfoSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn parse(words: &str, forward: bool) -> Option<NaiveDate> {
+        let words: Vec<&str> = words.split_whitespace().collect();
+        parse_date(&words, date(2026, 10, 19), forward)
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(parse("2026-12-24", true), Some(date(2026, 12, 24)));
+        assert_eq!(parse("today", true), Some(date(2026, 10, 19)));
+        assert_eq!(parse("tomorrow", true), Some(date(2026, 10, 20)));
+        assert_eq!(parse("yesterday", true), Some(date(2026, 10, 18)));
+        assert_eq!(parse("24 december 2027", true), Some(date(2027, 12, 24)));
+        assert_eq!(parse("dec 24th 2027", true), Some(date(2027, 12, 24)));
+        assert_eq!(parse("2026-02-30", true), None);
+        assert_eq!(parse("someday", true), None);
+    }
+
+    #[test]
+    fn dates_without_a_year() {
+        assert_eq!(parse("24 dec", true), Some(date(2026, 12, 24)));
+        assert_eq!(parse("1 january", true), Some(date(2027, 1, 1)));
+        assert_eq!(parse("1 january", false), Some(date(2026, 1, 1)));
+        assert_eq!(parse("24 dec", false), Some(date(2025, 12, 24)));
+        assert_eq!(parse("29 feb", true), Some(date(2028, 2, 29)));
+        assert_eq!(parse("29 feb", false), Some(date(2024, 2, 29)));
+    }
+
+    #[test]
+    fn offsets() {
+        let today = date(2026, 10, 19);
+        assert_eq!(apply_offset(today, "+90d"), Some(date(2027, 1, 17)));
+        assert_eq!(apply_offset(today, "-2w"), Some(date(2026, 10, 5)));
+        assert_eq!(apply_offset(today, "+1y-3d"), Some(date(2027, 10, 16)));
+        assert_eq!(apply_offset(today, "+3months"), Some(date(2027, 1, 19)));
+        assert_eq!(apply_offset(today, "−1day"), Some(date(2026, 10, 18)));
+
+        // months are clamped to their last day
+        assert_eq!(
+            apply_offset(date(2026, 1, 31), "+1m"),
+            Some(date(2026, 2, 28))
+        );
+
+        assert_eq!(apply_offset(today, "+90"), None);
+        assert_eq!(apply_offset(today, "90d"), None);
+        assert_eq!(apply_offset(today, "+2fortnights"), None);
+        assert_eq!(apply_offset(today, "+999999999y"), None);
+    }
+
+    #[test]
+    fn spans() {
+        assert_eq!(relative_days(0), "today");
+        assert_eq!(relative_days(-3), "3 days ago");
+        assert_eq!(weeks_and_days(66).as_deref(), Some("9 weeks and 3 days"));
+        assert_eq!(weeks_and_days(-7).as_deref(), Some("1 week"));
+        assert_eq!(weeks_and_days(6), None);
+    }
+}
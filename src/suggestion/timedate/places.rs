@@ -0,0 +1,341 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::{OffsetComponents, Tz, TZ_VARIANTS};
+
+use std::collections::HashMap;
+
+use crate::suggestion::matching::{bounded_distance, normalize, TYPO_BUDGET};
+use crate::suggestion::MatchLevel;
+
+// tzdb's zones and the countries they span, and the names of those countries
+const ZONES: &str = include_str!("zone1970.tab");
+const COUNTRIES: &str = include_str!("iso3166.tab");
+
+// cities without a zone of their own, "San Francisco" is on Los Angeles time
+const CITIES: &str = include_str!("cities.tab");
+
+// zone names in these regions end in a city, unlike "US/Pacific" or "Etc/GMT+5"
+const REGIONS: [&str; 9] = [
+    "Africa",
+    "America",
+    "Antarctica",
+    "Asia",
+    "Atlantic",
+    "Australia",
+    "Europe",
+    "Indian",
+    "Pacific",
+];
+
+// what countries are commonly called besides their name in iso3166.tab
+const COUNTRY_ALIASES: [(&str, &str); 16] = [
+    ("usa", "US"),
+    ("us", "US"),
+    ("america", "US"),
+    ("united states of america", "US"),
+    ("uk", "GB"),
+    ("united kingdom", "GB"),
+    ("great britain", "GB"),
+    ("england", "GB"),
+    ("scotland", "GB"),
+    ("wales", "GB"),
+    ("holland", "NL"),
+    ("czechia", "CZ"),
+    ("uae", "AE"),
+    ("emirates", "AE"),
+    ("burma", "MM"),
+    ("swaziland", "SZ"),
+];
+
+// countries like the US span a lot of zones, the rest are left out
+const MAX_CANDIDATES: usize = 8;
+
+/// A city or country and its time zone
+#[derive(Debug)]
+pub struct Place {
+    pub name: String,
+
+    // which part of a country spanning several zones, like "Eastern (most areas)"
+    pub detail: Option<String>,
+
+    pub tz: Tz,
+
+    // the name as it's matched against queries
+    key: String,
+}
+
+impl Place {
+    /// "San Francisco" or "United States, Eastern (most areas)"
+    pub fn label(&self) -> String {
+        match &self.detail {
+            Some(detail) => format!("{}, {}", self.name, detail),
+            None => self.name.clone(),
+        }
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref PLACES: Vec<Place> = build_index();
+}
+
+// normalized words without punctuation, "Bosnia & Herzegovina" becomes "bosnia herzegovina"
+fn search_key(name: &str) -> String {
+    normalize(name)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// "Korea (South)" is also found as "south korea" and "korea"
+fn country_keys(name: &str) -> Vec<String> {
+    let mut keys = vec![search_key(name)];
+    if let Some((base, rest)) = name.split_once(" (") {
+        let inner = rest.trim_end_matches(')');
+        keys.push(search_key(&format!("{} {}", inner, base)));
+        keys.push(search_key(base));
+    }
+    keys
+}
+
+fn build_index() -> Vec<Place> {
+    let mut places = Vec::new();
+    let mut add = |key: String, name: &str, detail: Option<&str>, tz: Tz| {
+        places.push(Place {
+            name: name.to_string(),
+            detail: detail.map(String::from),
+            tz,
+            key,
+        })
+    };
+
+    // "America/Argentina/Buenos_Aires" is where Buenos Aires is
+    for tz in TZ_VARIANTS {
+        let name = tz.name();
+        let in_region = REGIONS.iter().any(|region| {
+            name.strip_prefix(region)
+                .is_some_and(|r| r.starts_with('/'))
+        });
+        if !in_region {
+            continue;
+        }
+        let city = name.rsplit('/').next().unwrap_or(name).replace('_', " ");
+        add(search_key(&city), &city, None, tz);
+    }
+
+    for line in CITIES.lines().filter(|line| !line.starts_with('#')) {
+        if let Some((city, zone)) = line.split_once('\t') {
+            if let Ok(tz) = zone.parse::<Tz>() {
+                add(search_key(city), city, None, tz);
+            }
+        }
+    }
+
+    let names: HashMap<&str, &str> = COUNTRIES
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('\t'))
+        .collect();
+
+    // every zone of every country, the most populous first
+    let mut rows = Vec::new();
+    for line in ZONES.lines().filter(|line| !line.starts_with('#')) {
+        let mut fields = line.split('\t');
+        let (codes, zone) = match (fields.next(), fields.next()) {
+            (Some(codes), Some(zone)) => (codes, zone),
+            _ => continue,
+        };
+        let comment = fields.next();
+        if let Ok(tz) = zone.parse::<Tz>() {
+            rows.extend(
+                codes
+                    .split(',')
+                    .enumerate()
+                    .map(|(i, code)| (i == 0, code, tz, comment)),
+            );
+        }
+    }
+
+    // a zone's first country is the one it's mostly about, Europe/Zurich is Switzerland's zone
+    // and only covers a sliver of Germany
+    rows.sort_by_key(|(primary, ..)| !primary);
+
+    let mut zone_count: HashMap<&str, usize> = HashMap::new();
+    for (_, code, ..) in &rows {
+        *zone_count.entry(code).or_default() += 1;
+    }
+
+    for (_, code, tz, comment) in rows {
+        let name = match names.get(code) {
+            Some(name) => *name,
+            None => continue,
+        };
+        // the comments only describe the parts of countries with several zones
+        let detail = comment.filter(|_| zone_count[code] > 1);
+
+        let aliases = COUNTRY_ALIASES
+            .iter()
+            .filter(|(_, alias_code)| *alias_code == code)
+            .map(|(alias, _)| alias.to_string());
+        for key in country_keys(name).into_iter().chain(aliases) {
+            add(key, name, detail, tz);
+        }
+    }
+
+    places
+}
+
+/// Places matching the words of a query, best first: exact names, then names starting with
+/// the query and finally names off by a typo or two
+pub fn lookup(words: &[&str]) -> Vec<(MatchLevel, &'static Place)> {
+    let query = search_key(&words.join(" "));
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut found: Vec<(MatchLevel, &Place)> = PLACES
+        .iter()
+        .filter(|place| place.key == query)
+        .map(|place| (MatchLevel::Exact, place))
+        .collect();
+
+    if found.is_empty() && query.chars().count() >= 3 {
+        found = PLACES
+            .iter()
+            .filter(|place| place.key.starts_with(&query))
+            .map(|place| (MatchLevel::Contained, place))
+            .collect();
+    }
+
+    if found.is_empty() {
+        let chars: Vec<char> = query.chars().collect();
+        if let Some((_, max)) = TYPO_BUDGET.iter().find(|(len, _)| chars.len() >= *len) {
+            found = PLACES
+                .iter()
+                .filter_map(|place| {
+                    let distance = bounded_distance(&chars, &place.key, *max)?;
+                    Some((MatchLevel::Typo(distance as u8), place))
+                })
+                .collect();
+            found.sort_by_key(|(level, _)| *level);
+        }
+    }
+
+    // the same place can match under several of its names, and a clock per offset is enough
+    // for countries spanning lots of zones
+    let now = Utc::now().naive_utc();
+    let mut seen = Vec::new();
+    found.retain(|(_, place)| {
+        let offset = place.tz.offset_from_utc_datetime(&now);
+        let key = (&place.name, offset.base_utc_offset(), offset.dst_offset());
+        let new = !seen.contains(&key);
+        seen.push(key);
+        new
+    });
+    found.truncate(MAX_CANDIDATES);
+    found
+}
+
+/// A full zone name typed out, like "europe/berlin"
+pub fn zone_by_name(words: &[&str]) -> Option<Tz> {
+    let name = words.join("_");
+    TZ_VARIANTS
+        .iter()
+        .find(|tz| tz.name().eq_ignore_ascii_case(&name))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zones(words: &[&str]) -> Vec<(MatchLevel, &'static str)> {
+        lookup(words)
+            .into_iter()
+            .map(|(level, place)| (level, place.tz.name()))
+            .collect()
+    }
+
+    #[test]
+    fn cities() {
+        assert_eq!(
+            zones(&["buenos", "aires"]),
+            [(MatchLevel::Exact, "America/Argentina/Buenos_Aires")]
+        );
+        // not a zone of its own, it comes from cities.tab
+        assert_eq!(
+            zones(&["san", "francisco"]),
+            [(MatchLevel::Exact, "America/Los_Angeles")]
+        );
+        assert_eq!(
+            zones(&["buenos"]),
+            [(MatchLevel::Contained, "America/Argentina/Buenos_Aires")]
+        );
+    }
+
+    #[test]
+    fn countries() {
+        // Europe/Zurich covers a bit of Germany too, but at the same offset as Berlin
+        assert_eq!(zones(&["germany"]), [(MatchLevel::Exact, "Europe/Berlin")]);
+        assert_eq!(
+            zones(&["south", "korea"]),
+            [(MatchLevel::Exact, "Asia/Seoul")]
+        );
+    }
+
+    #[test]
+    fn countries_with_several_zones() {
+        let found = lookup(&["usa"]);
+        assert_eq!(found.len(), MAX_CANDIDATES);
+        assert_eq!(found[0].1.tz.name(), "America/New_York");
+        assert_eq!(found[0].1.label(), "United States, Eastern (most areas)");
+
+        let now = Utc::now().naive_utc();
+        let mut offsets: Vec<_> = found
+            .iter()
+            .map(|(level, place)| {
+                assert_eq!(*level, MatchLevel::Exact);
+                assert_eq!(place.name, "United States");
+                let offset = place.tz.offset_from_utc_datetime(&now);
+                (offset.base_utc_offset(), offset.dst_offset())
+            })
+            .collect();
+        offsets.sort();
+        offsets.dedup();
+        assert_eq!(offsets.len(), MAX_CANDIDATES, "one clock per offset");
+    }
+
+    #[test]
+    fn aliases() {
+        assert_eq!(zones(&["uk"]), [(MatchLevel::Exact, "Europe/London")]);
+        // tzdb files the Netherlands under Brussels
+        assert_eq!(
+            zones(&["holland"]),
+            [(MatchLevel::Exact, "Europe/Brussels")]
+        );
+    }
+
+    #[test]
+    fn typos() {
+        assert_eq!(zones(&["zurick"]), [(MatchLevel::Typo(1), "Europe/Zurich")]);
+        // swapped letters count as one typo
+        assert_eq!(
+            zones(&["buenos", "aries"]),
+            [(MatchLevel::Typo(1), "America/Argentina/Buenos_Aires")]
+        );
+        // too short to guess
+        assert!(zones(&["qx"]).is_empty());
+    }
+
+    #[test]
+    fn zone_names() {
+        assert_eq!(
+            zone_by_name(&["europe/berlin"]),
+            Some(chrono_tz::Europe::Berlin)
+        );
+        assert_eq!(
+            zone_by_name(&["america/new", "york"]),
+            Some(chrono_tz::America::New_York)
+        );
+        assert_eq!(zone_by_name(&["berlin"]), None);
+    }
+}
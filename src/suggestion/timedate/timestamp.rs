@@ -0,0 +1,320 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use iced::widget::{column, horizontal_space, row, text, vertical_space};
+use iced::{theme, Color, Length};
+
+use std::fmt::Display;
+
+use super::worldclock::Clock;
+use super::zone::{self, Zone};
+use super::*;
+
+// ISO 8601 variants besides RFC 3339, which chrono parses on its own
+const ISO_WITH_OFFSET: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f%z", "%Y-%m-%d %H:%M:%S%.f%z"];
+const ISO_WITHOUT_OFFSET: [&str; 3] = [
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// What a timestamp was given as, so it isn't just repeated back
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Seconds,
+    Milliseconds,
+    Microseconds,
+    Nanoseconds,
+    Iso,
+    Rfc2822,
+}
+
+// epoch seconds, milliseconds, microseconds or nanoseconds, told apart by their length.
+// Shorter numbers are more likely to be anything but a timestamp.
+fn parse_epoch(query: &str) -> Option<(DateTime<Utc>, Format)> {
+    let query = query.strip_prefix('@').unwrap_or(query);
+    let (whole, fraction) = query.split_once('.').unwrap_or((query, ""));
+    if !whole
+        .chars()
+        .chain(fraction.chars())
+        .all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let (format, nanos_per_unit) = match whole.len() {
+        9..=10 => (Format::Seconds, 1_000_000_000),
+        11..=13 => (Format::Milliseconds, 1_000_000),
+        14..=16 => (Format::Microseconds, 1_000),
+        17..=19 => (Format::Nanoseconds, 1),
+        _ => return None,
+    };
+    let whole: i64 = whole.parse().ok()?;
+
+    // only up to nanosecond precision
+    let digits = nanos_per_unit.to_string().len() - 1;
+    let fraction: String = fraction
+        .chars()
+        .chain("000000000".chars())
+        .take(digits)
+        .collect();
+    let fraction: i64 = if fraction.is_empty() {
+        0
+    } else {
+        fraction.parse().ok()?
+    };
+
+    let nanos = whole.checked_mul(nanos_per_unit)?.checked_add(fraction)?;
+    Some((Utc.timestamp_nanos(nanos), format))
+}
+
+// RFC 3339, looser ISO 8601 and RFC 2822. Times without an offset are taken as local time,
+// which the bool tells.
+fn parse_formatted(query: &str) -> Option<(DateTime<Utc>, Format, bool)> {
+    let parsed = parse_any_format(query)?;
+
+    // `%Y` takes signed years of any length, RFC 2822 can't express those (chrono panics)
+    (0..=9999).contains(&parsed.0.year()).then_some(parsed)
+}
+
+fn parse_any_format(query: &str) -> Option<(DateTime<Utc>, Format, bool)> {
+    let iso = query.to_uppercase();
+
+    if let Ok(time) = DateTime::parse_from_rfc3339(&iso) {
+        return Some((time.with_timezone(&Utc), Format::Iso, false));
+    }
+    for format in ISO_WITH_OFFSET {
+        if let Ok(time) = DateTime::parse_from_str(&iso, format) {
+            return Some((time.with_timezone(&Utc), Format::Iso, false));
+        }
+    }
+    for format in ISO_WITHOUT_OFFSET {
+        if let Ok(time) = NaiveDateTime::parse_from_str(&iso, format) {
+            let time = Local.from_local_datetime(&time).earliest()?;
+            return Some((time.with_timezone(&Utc), Format::Iso, true));
+        }
+    }
+    if let Ok(time) = DateTime::parse_from_rfc2822(query) {
+        return Some((time.with_timezone(&Utc), Format::Rfc2822, false));
+    }
+    None
+}
+
+// "3 hours ago" or "in 2 days", in the largest unit that fits
+fn relative(seconds: i64) -> String {
+    let units = [
+        (86_400, "day"),
+        (3_600, "hour"),
+        (60, "minute"),
+        (1, "second"),
+    ];
+    let (size, unit) = units
+        .iter()
+        .find(|(size, _)| seconds.abs() >= *size)
+        .unwrap_or(&(1, "second"));
+    let amount = seconds.abs() / size;
+    let plural = if amount == 1 { "" } else { "s" };
+    match seconds {
+        0 => String::from("now"),
+        seconds if seconds > 0 => format!("in {} {}{}", amount, unit, plural),
+        _ => format!("{} {}{} ago", amount, unit, plural),
+    }
+}
+
+/// One form of a timestamp from a query, like "1792418580", "2026-10-19T14:03:00Z" or
+/// "Mon, 19 Oct 2026 14:03:00 +0000". Every form and zone gets a suggestion of its own.
+#[derive(Debug)]
+pub struct TimestampSuggestion {
+    label: String,
+
+    // what gets copied
+    value: String,
+
+    note: Option<String>,
+}
+
+impl TimestampSuggestion {
+    // `query` is expected to be trimmed, but not normalized
+    pub fn from_query(query: &str, clocks: &[Clock]) -> Vec<Self> {
+        let (time, format, assumed_local) = match parse_epoch(query) {
+            Some((time, format)) => (time, format, false),
+            None => match parse_formatted(query) {
+                Some(parsed) => parsed,
+                None => return Vec::new(),
+            },
+        };
+
+        let iso = |time: DateTime<Utc>, zone: &Zone| {
+            time.with_timezone(&zone.offset(time))
+                .to_rfc3339_opts(SecondsFormat::AutoSi, false)
+        };
+        let row = |label: &str, value: String, note: Option<String>| Self {
+            label: label.to_string(),
+            value,
+            note,
+        };
+
+        let local = Zone::Local.offset(time).local_minus_utc();
+        let mut local_note = relative((time - Utc::now()).num_seconds());
+        if assumed_local {
+            local_note.push_str(", no offset given so it's read as local time");
+        }
+
+        let mut rows = vec![
+            row(
+                "Local time",
+                iso(time, &Zone::Local),
+                Some(format!("{} · {}", zone::format_offset(local), local_note)),
+            ),
+            row(
+                "UTC",
+                time.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                None,
+            ),
+        ];
+        for clock in clocks {
+            let zone = Zone::Named(clock.tz);
+            rows.push(row(
+                &clock.name,
+                iso(time, &zone),
+                Some(format!("{} ({})", clock.tz.name(), zone.abbreviation(time))),
+            ));
+        }
+        if format != Format::Seconds {
+            rows.push(row("Unix time", time.timestamp().to_string(), None));
+        }
+        if format != Format::Milliseconds {
+            rows.push(row(
+                "Unix time (ms)",
+                time.timestamp_millis().to_string(),
+                None,
+            ));
+        }
+        if format != Format::Rfc2822 {
+            rows.push(row("RFC 2822", time.to_rfc2822(), None));
+        }
+        rows
+    }
+}
+
+impl Suggestion for TimestampSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let grey = theme::Text::Color(Color::from([0.6, 0.6, 0.6]));
+
+        let mut lines = column![row![
+            text(&self.label).width(Length::Fixed(160f32)).style(grey),
+            text(&self.value),
+        ]];
+        if let Some(note) = &self.note {
+            lines = lines.push(row![
+                horizontal_space(Length::Fixed(160f32)),
+                text(note).size(14).style(grey),
+            ]);
+        }
+
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                lines,
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        Ok(Some(LanchMessage::CopyToClipboard(self.value.clone())))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for TimestampSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timestamp")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn epoch(query: &str) -> Option<(i64, u32, Format)> {
+        parse_epoch(query)
+            .map(|(time, format)| (time.timestamp(), time.timestamp_subsec_nanos(), format))
+    }
+
+    #[test]
+    fn epoch_units() {
+        assert_eq!(
+            epoch("1792418580"),
+            Some((1_792_418_580, 0, Format::Seconds))
+        );
+        assert_eq!(
+            epoch("@1792418580"),
+            Some((1_792_418_580, 0, Format::Seconds))
+        );
+        assert_eq!(
+            epoch("1792418580.5"),
+            Some((1_792_418_580, 500_000_000, Format::Seconds))
+        );
+        assert_eq!(
+            epoch("1792418580123"),
+            Some((1_792_418_580, 123_000_000, Format::Milliseconds))
+        );
+        assert_eq!(
+            epoch("1792418580123456"),
+            Some((1_792_418_580, 123_456_000, Format::Microseconds))
+        );
+        assert_eq!(
+            epoch("1792418580123456789"),
+            Some((1_792_418_580, 123_456_789, Format::Nanoseconds))
+        );
+    }
+
+    #[test]
+    fn not_epochs() {
+        // too short to be anything but a number
+        assert_eq!(epoch("12345"), None);
+        assert_eq!(epoch("17924185801234567890"), None);
+        assert_eq!(epoch("-1792418580"), None);
+        assert_eq!(epoch("1792418580.5s"), None);
+    }
+
+    #[test]
+    fn formats() {
+        let parsed = |query| {
+            parse_formatted(query).map(|(time, format, local)| (time.timestamp(), format, local))
+        };
+        assert_eq!(
+            parsed("2026-10-19T14:03:00Z"),
+            Some((1_792_418_580, Format::Iso, false))
+        );
+        assert_eq!(
+            parsed("2026-10-19t16:03:00+02:00"),
+            Some((1_792_418_580, Format::Iso, false))
+        );
+        assert_eq!(
+            parsed("2026-10-19T19:33:00+0530"),
+            Some((1_792_418_580, Format::Iso, false))
+        );
+        assert_eq!(
+            parsed("Mon, 19 Oct 2026 14:03:00 +0000"),
+            Some((1_792_418_580, Format::Rfc2822, false))
+        );
+        assert!(matches!(
+            parsed("2026-10-19 14:03:00"),
+            Some((_, Format::Iso, true))
+        ));
+        assert_eq!(parsed("2026-10-19"), None);
+    }
+
+    #[test]
+    fn years_rfc_2822_cannot_express() {
+        assert_eq!(parse_formatted("-0001-01-01T00:00"), None);
+        assert_eq!(parse_formatted("+10000-01-01T00:00"), None);
+        assert!(TimestampSuggestion::from_query("+10000-01-01T00:00:00Z", &[]).is_empty());
+    }
+}
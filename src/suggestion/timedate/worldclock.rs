@@ -0,0 +1,144 @@
+use chrono::{Local, Offset, Utc};
+use chrono_tz::Tz;
+use iced::widget::{column, horizontal_space, row, text, vertical_space};
+use iced::{theme, Color, Length};
+use serde::Deserialize;
+
+use std::fmt::Display;
+
+use super::places;
+use super::*;
+
+/// An entry of `[[modules.timedate.clocks]]`
+#[derive(Debug, Deserialize)]
+pub struct ClockConfig {
+    name: String,
+
+    // a zone name like "America/New_York", or a city or country
+    zone: String,
+}
+
+/// A named zone shown when asking for the time
+#[derive(Debug)]
+pub struct Clock {
+    pub name: String,
+    pub tz: Tz,
+}
+
+impl Clock {
+    pub fn from_config(config: ClockConfig) -> Result<Self, String> {
+        let zone = normalize(&config.zone);
+        let words: Vec<&str> = zone.split_whitespace().collect();
+        // no guessing here, a typo should show up as an error rather than a wrong clock
+        let tz = places::zone_by_name(&words)
+            .or_else(|| {
+                places::lookup(&words)
+                    .into_iter()
+                    .find(|(level, _)| *level == MatchLevel::Exact)
+                    .map(|(_, place)| place.tz)
+            })
+            .ok_or_else(|| format!("no time zone found for `{}`", config.zone))?;
+
+        Ok(Self {
+            name: config.name,
+            tz,
+        })
+    }
+}
+
+// "+6h", "-3:30h" or "same time" relative to the local zone
+fn relative_offset(seconds: i32) -> String {
+    if seconds == 0 {
+        return String::from("same time");
+    }
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    match minutes % 60 {
+        0 => format!("{}{}h", sign, minutes / 60),
+        rest => format!("{}{}:{:02}h", sign, minutes / 60, rest),
+    }
+}
+
+/// The local time along with every configured clock, a row each
+#[derive(Debug)]
+pub struct WorldClockSuggestion {
+    clocks: Arc<Vec<Clock>>,
+}
+
+impl WorldClockSuggestion {
+    pub fn new(clocks: Arc<Vec<Clock>>) -> Self {
+        Self { clocks }
+    }
+}
+
+impl Suggestion for WorldClockSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let now = Utc::now();
+        let local = now.with_timezone(&Local);
+        let grey = theme::Text::Color(Color::from([0.6, 0.6, 0.6]));
+
+        let mut rows = column![row![
+            text("Local time").width(Length::Fixed(200f32)),
+            text(local.format("%H:%M:%S")),
+        ]]
+        .spacing(4);
+
+        for clock in self.clocks.iter() {
+            let there = now.with_timezone(&clock.tz);
+            let offset = there.offset().fix().local_minus_utc() - local.offset().local_minus_utc();
+            let day = match (there.date_naive() - local.date_naive()).num_days() {
+                1 => "tomorrow",
+                -1 => "yesterday",
+                _ => "",
+            };
+
+            rows = rows.push(row![
+                text(&clock.name).width(Length::Fixed(200f32)),
+                text(there.format("%H:%M:%S")).width(Length::Fixed(100f32)),
+                text(relative_offset(offset))
+                    .width(Length::Fixed(90f32))
+                    .style(grey),
+                text(day).style(grey),
+            ]);
+        }
+
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                rows,
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    // a line per clock, like "Design team 05:03"
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        let now = Utc::now();
+        let mut lines = vec![format!(
+            "Local time {}",
+            now.with_timezone(&Local).format("%H:%M")
+        )];
+        for clock in self.clocks.iter() {
+            let there = now.with_timezone(&clock.tz);
+            lines.push(format!("{} {}", clock.name, there.format("%H:%M")));
+        }
+        Ok(Some(LanchMessage::CopyToClipboard(lines.join("\n"))))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+
+    fn live(&self) -> bool {
+        true
+    }
+}
+
+impl Display for WorldClockSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "World clock")
+    }
+}
@@ -0,0 +1,313 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+
+use super::places;
+
+/// Abbreviations people use for time zones, with their offset in minutes and the region using
+/// them, if it switches between standard and daylight saving time
+const ABBREVIATIONS: [(&str, i32, Option<Tz>); 38] = [
+    ("utc", 0, None),
+    ("gmt", 0, None),
+    ("z", 0, None),
+    ("wet", 0, Some(Tz::Europe__Lisbon)),
+    ("west", 60, Some(Tz::Europe__Lisbon)),
+    ("bst", 60, Some(Tz::Europe__London)),
+    ("ist", 330, None),
+    ("cet", 60, Some(Tz::Europe__Paris)),
+    ("cest", 120, Some(Tz::Europe__Paris)),
+    ("eet", 120, Some(Tz::Europe__Athens)),
+    ("eest", 180, Some(Tz::Europe__Athens)),
+    ("msk", 180, None),
+    ("gst", 240, None),
+    ("pkt", 300, None),
+    ("ict", 420, None),
+    ("wib", 420, None),
+    ("hkt", 480, None),
+    ("sgt", 480, None),
+    ("awst", 480, None),
+    ("jst", 540, None),
+    ("kst", 540, None),
+    ("acst", 570, Some(Tz::Australia__Adelaide)),
+    ("acdt", 630, Some(Tz::Australia__Adelaide)),
+    ("aest", 600, Some(Tz::Australia__Sydney)),
+    ("aedt", 660, Some(Tz::Australia__Sydney)),
+    ("nzst", 720, Some(Tz::Pacific__Auckland)),
+    ("nzdt", 780, Some(Tz::Pacific__Auckland)),
+    ("hst", -600, None),
+    ("akst", -540, Some(Tz::America__Anchorage)),
+    ("akdt", -480, Some(Tz::America__Anchorage)),
+    ("pst", -480, Some(Tz::America__Los_Angeles)),
+    ("pdt", -420, Some(Tz::America__Los_Angeles)),
+    ("mst", -420, Some(Tz::America__Denver)),
+    ("mdt", -360, Some(Tz::America__Denver)),
+    ("cst", -360, Some(Tz::America__Chicago)),
+    ("cdt", -300, Some(Tz::America__Chicago)),
+    ("est", -300, Some(Tz::America__New_York)),
+    ("edt", -240, Some(Tz::America__New_York)),
+];
+
+/// Names for regions rather than a fixed offset, these follow daylight saving time
+const REGIONS: [(&str, Tz); 8] = [
+    ("pt", Tz::America__Los_Angeles),
+    ("pacific", Tz::America__Los_Angeles),
+    ("mt", Tz::America__Denver),
+    ("mountain", Tz::America__Denver),
+    ("ct", Tz::America__Chicago),
+    ("central", Tz::America__Chicago),
+    ("et", Tz::America__New_York),
+    ("eastern", Tz::America__New_York),
+];
+
+/// The zone of a city or country, "new york" finds `America/New_York` and so does "usa"
+pub fn find_timezone(words: &[&str]) -> Option<Tz> {
+    places::zone_by_name(words).or_else(|| places::lookup(words).first().map(|(_, place)| place.tz))
+}
+
+// "utc+2", "gmt-5:30" or "+0530"
+fn parse_offset(s: &str) -> Option<i32> {
+    let rest = s
+        .strip_prefix("utc")
+        .or_else(|| s.strip_prefix("gmt"))
+        .unwrap_or(s);
+    let (sign, rest) = match rest.chars().next()? {
+        '+' => (1, &rest[1..]),
+        '-' | '−' => (-1, &rest[rest.chars().next()?.len_utf8()..]),
+        _ => return None,
+    };
+    // the numbers would take another sign of their own ("utc+-2")
+    if !rest.chars().all(|c| c.is_ascii_digit() || c == ':') {
+        return None;
+    }
+
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+
+    (hours <= 14 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+/// "UTC+2" or "UTC-3:30"
+pub fn format_offset(seconds: i32) -> String {
+    if seconds == 0 {
+        return String::from("UTC");
+    }
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let minutes = seconds.abs() / 60;
+    match minutes % 60 {
+        0 => format!("UTC{}{}", sign, minutes / 60),
+        rest => format!("UTC{}{}:{:02}", sign, minutes / 60, rest),
+    }
+}
+
+/// Where a time is, as far as a query is concerned
+#[derive(Debug, Clone)]
+pub enum Zone {
+    Local,
+    Named(Tz),
+
+    // an abbreviation or offset, with what the user called it and the region it belongs to
+    Fixed(FixedOffset, String, Option<Tz>),
+}
+
+impl Zone {
+    /// `words` are expected to be normalized
+    pub fn parse(words: &[&str]) -> Option<Self> {
+        let joined = words.concat();
+
+        if joined == "local" || joined == "here" {
+            return Some(Self::Local);
+        }
+        if let Some((_, tz)) = REGIONS.iter().find(|(name, _)| *name == joined) {
+            return Some(Self::Named(*tz));
+        }
+        if let Some((name, minutes, region)) = ABBREVIATIONS.iter().find(|(n, ..)| *n == joined) {
+            let offset = FixedOffset::east_opt(minutes * 60)?;
+            return Some(Self::Fixed(offset, name.to_uppercase(), *region));
+        }
+        if let Some(minutes) = parse_offset(&joined) {
+            let offset = FixedOffset::east_opt(minutes * 60)?;
+            return Some(Self::Fixed(offset, format_offset(minutes * 60), None));
+        }
+
+        find_timezone(words).map(Self::Named)
+    }
+
+    /// "New York", "PST" or "local"
+    pub fn name(&self) -> String {
+        match self {
+            Zone::Local => String::from("local"),
+            Zone::Named(tz) => tz
+                .name()
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .replace('_', " "),
+            Zone::Fixed(_, name, _) => name.clone(),
+        }
+    }
+
+    /// The offset in effect at `at`
+    pub fn offset(&self, at: DateTime<Utc>) -> FixedOffset {
+        match self {
+            Zone::Local => at.with_timezone(&Local).offset().fix(),
+            Zone::Named(tz) => at.with_timezone(tz).offset().fix(),
+            Zone::Fixed(offset, ..) => *offset,
+        }
+    }
+
+    /// "EDT" for named zones, the offset for everything else
+    pub fn abbreviation(&self, at: DateTime<Utc>) -> String {
+        match self {
+            Zone::Named(tz) => {
+                let offset = tz.offset_from_utc_datetime(&at.naive_utc());
+                let abbreviation = offset.abbreviation();
+                // zones without a common abbreviation are given as "+03"
+                if abbreviation.starts_with(['+', '-']) {
+                    format_offset(self.offset(at).local_minus_utc())
+                } else {
+                    abbreviation.to_string()
+                }
+            }
+            _ => format_offset(self.offset(at).local_minus_utc()),
+        }
+    }
+
+    /// A wall clock time in this zone, the earlier one if it happens twice when the clocks go
+    /// back and none at all if it's skipped when they go forward
+    pub fn localize(&self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let local = match self {
+            Zone::Local => Local
+                .from_local_datetime(&time)
+                .earliest()?
+                .with_timezone(&Utc),
+            Zone::Named(tz) => tz
+                .from_local_datetime(&time)
+                .earliest()?
+                .with_timezone(&Utc),
+            Zone::Fixed(offset, ..) => offset
+                .from_local_datetime(&time)
+                .single()?
+                .with_timezone(&Utc),
+        };
+        Some(local)
+    }
+
+    /// The date and time here at `at`
+    pub fn wall_clock(&self, at: DateTime<Utc>) -> NaiveDateTime {
+        at.with_timezone(&self.offset(at)).naive_local()
+    }
+
+    /// Notes on daylight saving time, like a region being on summer time or an abbreviation
+    /// that isn't in effect at the moment
+    pub fn dst_note(&self, at: DateTime<Utc>) -> Option<String> {
+        match self {
+            Zone::Named(tz) => {
+                let offset = tz.offset_from_utc_datetime(&at.naive_utc());
+                (!offset.dst_offset().is_zero()).then(|| {
+                    format!(
+                        "{} is on daylight saving time ({})",
+                        self.name(),
+                        self.abbreviation(at)
+                    )
+                })
+            }
+            Zone::Fixed(offset, name, Some(region)) => {
+                let current = Zone::Named(*region);
+                (current.offset(at) != *offset).then(|| {
+                    format!(
+                        "{} isn't in use right now, {} is on {} ({})",
+                        name,
+                        current.name(),
+                        current.abbreviation(at),
+                        format_offset(current.offset(at).local_minus_utc())
+                    )
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offset("utc+2"), Some(120));
+        assert_eq!(parse_offset("gmt-5:30"), Some(-330));
+        assert_eq!(parse_offset("+0530"), Some(330));
+        assert_eq!(parse_offset("utc−3"), Some(-180));
+        assert_eq!(parse_offset("utc+15"), None);
+        assert_eq!(parse_offset("utc+2:75"), None);
+        assert_eq!(parse_offset("utc"), None);
+        assert_eq!(parse_offset("utc++2"), None);
+        assert_eq!(parse_offset("utc+-2"), None);
+        assert_eq!(parse_offset("utc+2:-3"), None);
+        assert_eq!(parse_offset("+a€"), None);
+        assert_eq!(parse_offset("2"), None);
+    }
+
+    #[test]
+    fn formatted_offsets() {
+        assert_eq!(format_offset(0), "UTC");
+        assert_eq!(format_offset(7200), "UTC+2");
+        assert_eq!(format_offset(-12600), "UTC-3:30");
+    }
+
+    #[test]
+    fn zones() {
+        assert!(matches!(Zone::parse(&["local"]), Some(Zone::Local)));
+        assert!(matches!(
+            Zone::parse(&["pt"]),
+            Some(Zone::Named(Tz::America__Los_Angeles))
+        ));
+        assert!(matches!(
+            Zone::parse(&["new", "york"]),
+            Some(Zone::Named(Tz::America__New_York))
+        ));
+        assert!(matches!(
+            Zone::parse(&["europe/berlin"]),
+            Some(Zone::Named(Tz::Europe__Berlin))
+        ));
+
+        match Zone::parse(&["pst"]) {
+            Some(Zone::Fixed(offset, name, region)) => {
+                assert_eq!(offset.local_minus_utc(), -8 * 3600);
+                assert_eq!(name, "PST");
+                assert_eq!(region, Some(Tz::America__Los_Angeles));
+            }
+            zone => panic!("{:?}", zone),
+        }
+    }
+
+    #[test]
+    fn daylight_saving_time_notes() {
+        let summer = Utc.with_ymd_and_hms(2026, 7, 1, 12, 0, 0).unwrap();
+        let winter = Utc.with_ymd_and_hms(2026, 1, 15, 12, 0, 0).unwrap();
+
+        let london = Zone::Named(Tz::Europe__London);
+        assert_eq!(london.abbreviation(summer), "BST");
+        assert!(london.dst_note(summer).is_some());
+        assert!(london.dst_note(winter).is_none());
+
+        // PST is only in use in winter
+        let pst = Zone::parse(&["pst"]).unwrap();
+        assert!(pst.dst_note(winter).is_none());
+        assert!(pst.dst_note(summer).unwrap().contains("PDT"));
+    }
+
+    #[test]
+    fn skipped_wall_clock_times() {
+        let berlin = Zone::Named(Tz::Europe__Berlin);
+        let skipped = chrono::NaiveDate::from_ymd_opt(2026, 3, 29)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert!(berlin.localize(skipped).is_none());
+    }
+}
@@ -0,0 +1,421 @@
+use iced::widget::{column, horizontal_space, row, text, vertical_space};
+use iced::Length;
+use rust_decimal::prelude::*;
+
+use std::fmt::Display;
+use std::sync::Arc;
+
+use super::*;
+
+// digits after the decimal point shown for results of one and up, smaller ones keep this many
+// significant digits instead
+const PRECISION: u32 = 10;
+
+// words that separate the unit converted from and the one converted to
+pub const SEPARATORS: [&str; 5] = ["to", "in", "as", "into", "->"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quantity {
+    Length,
+    Mass,
+    Temperature,
+    Volume,
+    Data,
+    Speed,
+    Time,
+    Area,
+}
+
+struct Unit {
+    // the first name is the one shown in results
+    names: &'static [&'static str],
+    quantity: Quantity,
+
+    // how many base units (meter, kilogram, kelvin, liter, byte, m/s, second, m²) one of these
+    // is, written as a fraction where decimals can't be exact
+    factor: &'static str,
+
+    // added before scaling, only temperatures need it
+    offset: &'static str,
+}
+
+const fn unit(names: &'static [&'static str], quantity: Quantity, factor: &'static str) -> Unit {
+    Unit {
+        names,
+        quantity,
+        factor,
+        offset: "0",
+    }
+}
+
+// bytes go before bits, "mb" is far more likely to mean megabytes
+const UNITS: &[Unit] = &[
+    unit(&["m", "meter", "metre"], Quantity::Length, "1"),
+    unit(&["km", "kilometer", "kilometre"], Quantity::Length, "1000"),
+    unit(
+        &["cm", "centimeter", "centimetre"],
+        Quantity::Length,
+        "0.01",
+    ),
+    unit(
+        &["mm", "millimeter", "millimetre"],
+        Quantity::Length,
+        "0.001",
+    ),
+    unit(
+        &["µm", "um", "micrometer", "micron"],
+        Quantity::Length,
+        "0.000001",
+    ),
+    unit(&["nm", "nanometer"], Quantity::Length, "0.000000001"),
+    unit(&["in", "inch", "\""], Quantity::Length, "0.0254"),
+    unit(&["ft", "foot", "feet", "'"], Quantity::Length, "0.3048"),
+    unit(&["yd", "yard"], Quantity::Length, "0.9144"),
+    unit(&["mi", "mile"], Quantity::Length, "1609.344"),
+    unit(&["nmi", "nautical mile"], Quantity::Length, "1852"),
+    unit(&["kg", "kilogram", "kilo"], Quantity::Mass, "1"),
+    unit(&["g", "gram", "gramme"], Quantity::Mass, "0.001"),
+    unit(&["mg", "milligram"], Quantity::Mass, "0.000001"),
+    unit(&["t", "tonne", "metric ton"], Quantity::Mass, "1000"),
+    unit(&["lb", "lbs", "pound"], Quantity::Mass, "0.45359237"),
+    unit(&["oz", "ounce"], Quantity::Mass, "0.028349523125"),
+    unit(&["st", "stone"], Quantity::Mass, "6.35029318"),
+    unit(&["K", "kelvin"], Quantity::Temperature, "1"),
+    Unit {
+        names: &["°C", "C", "celsius", "degC"],
+        quantity: Quantity::Temperature,
+        factor: "1",
+        offset: "273.15",
+    },
+    Unit {
+        names: &["°F", "F", "fahrenheit", "degF"],
+        quantity: Quantity::Temperature,
+        factor: "5/9",
+        offset: "459.67",
+    },
+    unit(&["l", "L", "liter", "litre"], Quantity::Volume, "1"),
+    unit(
+        &["ml", "mL", "milliliter", "millilitre"],
+        Quantity::Volume,
+        "0.001",
+    ),
+    unit(
+        &["cl", "centiliter", "centilitre"],
+        Quantity::Volume,
+        "0.01",
+    ),
+    unit(&["dl", "deciliter", "decilitre"], Quantity::Volume, "0.1"),
+    unit(
+        &["m³", "m3", "cubic meter", "cubic metre"],
+        Quantity::Volume,
+        "1000",
+    ),
+    unit(
+        &["cm³", "cm3", "cc", "cubic centimeter"],
+        Quantity::Volume,
+        "0.001",
+    ),
+    unit(&["gal", "gallon"], Quantity::Volume, "3.785411784"),
+    unit(&["qt", "quart"], Quantity::Volume, "0.946352946"),
+    unit(&["pt", "pint"], Quantity::Volume, "0.473176473"),
+    unit(&["cup"], Quantity::Volume, "0.2365882365"),
+    unit(
+        &["fl oz", "floz", "fluid ounce"],
+        Quantity::Volume,
+        "0.0295735295625",
+    ),
+    unit(
+        &["tbsp", "tablespoon"],
+        Quantity::Volume,
+        "0.01478676478125",
+    ),
+    unit(&["tsp", "teaspoon"], Quantity::Volume, "0.00492892159375"),
+    unit(&["B", "byte"], Quantity::Data, "1"),
+    unit(&["kB", "KB", "kilobyte"], Quantity::Data, "1000"),
+    unit(&["MB", "megabyte"], Quantity::Data, "1000000"),
+    unit(&["GB", "gigabyte"], Quantity::Data, "1000000000"),
+    unit(&["TB", "terabyte"], Quantity::Data, "1000000000000"),
+    unit(&["PB", "petabyte"], Quantity::Data, "1000000000000000"),
+    unit(&["KiB", "kibibyte"], Quantity::Data, "1024"),
+    unit(&["MiB", "mebibyte"], Quantity::Data, "1048576"),
+    unit(&["GiB", "gibibyte"], Quantity::Data, "1073741824"),
+    unit(&["TiB", "tebibyte"], Quantity::Data, "1099511627776"),
+    unit(&["PiB", "pebibyte"], Quantity::Data, "1125899906842624"),
+    unit(&["b", "bit"], Quantity::Data, "0.125"),
+    unit(&["kb", "Kb", "kbit", "kilobit"], Quantity::Data, "125"),
+    unit(&["Mb", "Mbit", "megabit"], Quantity::Data, "125000"),
+    unit(&["Gb", "Gbit", "gigabit"], Quantity::Data, "125000000"),
+    unit(&["m/s", "mps"], Quantity::Speed, "1"),
+    unit(&["km/h", "kmh", "kph"], Quantity::Speed, "5/18"),
+    unit(&["mph", "mi/h"], Quantity::Speed, "0.44704"),
+    unit(&["kn", "kt", "knot"], Quantity::Speed, "463/900"),
+    unit(&["ft/s", "fps"], Quantity::Speed, "0.3048"),
+    unit(&["ns", "nanosecond"], Quantity::Time, "0.000000001"),
+    unit(&["µs", "us", "microsecond"], Quantity::Time, "0.000001"),
+    unit(&["ms", "millisecond"], Quantity::Time, "0.001"),
+    unit(&["s", "sec", "second"], Quantity::Time, "1"),
+    unit(&["min", "minute"], Quantity::Time, "60"),
+    unit(&["h", "hr", "hour"], Quantity::Time, "3600"),
+    unit(&["d", "day"], Quantity::Time, "86400"),
+    unit(&["wk", "week"], Quantity::Time, "604800"),
+    // averages over the 400 year cycle of the gregorian calendar
+    unit(&["mo", "month"], Quantity::Time, "2629746"),
+    unit(&["yr", "year"], Quantity::Time, "31556952"),
+    unit(
+        &["m²", "m2", "sqm", "square meter", "square metre"],
+        Quantity::Area,
+        "1",
+    ),
+    unit(
+        &["km²", "km2", "square kilometer"],
+        Quantity::Area,
+        "1000000",
+    ),
+    unit(
+        &["cm²", "cm2", "square centimeter"],
+        Quantity::Area,
+        "0.0001",
+    ),
+    unit(&["ha", "hectare"], Quantity::Area, "10000"),
+    unit(&["acre", "ac"], Quantity::Area, "4046.8564224"),
+    unit(
+        &["ft²", "ft2", "sqft", "sq ft", "square foot", "square feet"],
+        Quantity::Area,
+        "0.09290304",
+    ),
+    unit(
+        &["in²", "in2", "sq in", "square inch"],
+        Quantity::Area,
+        "0.00064516",
+    ),
+    unit(
+        &["yd²", "yd2", "sq yd", "square yard"],
+        Quantity::Area,
+        "0.83612736",
+    ),
+    unit(
+        &["mi²", "mi2", "sq mi", "square mile"],
+        Quantity::Area,
+        "2589988.110336",
+    ),
+];
+
+// "5/9" as numerator and denominator, so converting doesn't pile up rounding errors
+fn fraction(s: &str) -> (Decimal, Decimal) {
+    let (num, den) = s.split_once('/').unwrap_or((s, "1"));
+    (
+        Decimal::from_str(num).unwrap_or_default(),
+        Decimal::from_str(den).unwrap_or(Decimal::ONE),
+    )
+}
+
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let find = |name: &str| {
+        UNITS.iter().find(|u| u.names.contains(&name)).or_else(|| {
+            UNITS
+                .iter()
+                .find(|u| u.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        })
+    };
+
+    // plurals of the spelled out names, "inches" or "hours"
+    find(name).or_else(|| {
+        [name.strip_suffix("es"), name.strip_suffix('s')]
+            .into_iter()
+            .flatten()
+            .filter(|singular| singular.chars().count() > 2)
+            .find_map(find)
+    })
+}
+
+fn convert(value: Decimal, from: &Unit, to: &Unit) -> Option<Decimal> {
+    let (from_num, from_den) = fraction(from.factor);
+    let (to_num, to_den) = fraction(to.factor);
+    let from_offset = Decimal::from_str(from.offset).ok()?;
+    let to_offset = Decimal::from_str(to.offset).ok()?;
+
+    // value in base units is (value + offset) * factor, going back divides by the other factor
+    let scaled = value
+        .checked_add(from_offset)?
+        .checked_mul(from_num)?
+        .checked_mul(to_den)?
+        .checked_div(from_den.checked_mul(to_num)?)?;
+    scaled.checked_sub(to_offset)
+}
+
+fn format_value(value: Decimal) -> String {
+    let rounded = if value.abs() < Decimal::ONE {
+        value.round_sf(PRECISION).unwrap_or(value)
+    } else {
+        value.round_dp(PRECISION)
+    };
+    rounded.normalize().to_string()
+}
+
+// "12in" or "-40 C", the number has to come first
+pub fn split_number(query: &str) -> Option<(Decimal, &str)> {
+    let end = query
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && "+-".contains(*c))))
+        .map_or(query.len(), |(i, _)| i);
+
+    let value = Decimal::from_str(&query[..end]).ok()?;
+    Some((value, &query[end..]))
+}
+
+/// Parses "<value> <unit> to <unit>"
+fn parse(query: &str) -> Option<(Decimal, &'static Unit, &'static Unit)> {
+    let (value, rest) = split_number(query.trim())?;
+    let words: Vec<&str> = rest.split_whitespace().collect();
+
+    // "in" is both a unit and a separator, so every separator is tried until both sides make
+    // sense
+    words
+        .iter()
+        .enumerate()
+        .filter(|(i, word)| *i > 0 && SEPARATORS.contains(&word.to_lowercase().as_str()))
+        .find_map(|(i, _)| {
+            let from = find_unit(&words[..i].join(" "))?;
+            let to = find_unit(&words[i + 1..].join(" "))?;
+            (from.quantity == to.quantity).then_some((value, from, to))
+        })
+}
+
+/// The result of a unit conversion
+#[derive(Debug)]
+pub struct UnitSuggestion {
+    value: String,
+    from: &'static str,
+    result: String,
+    to: &'static str,
+}
+
+impl Suggestion for UnitSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        column![
+            vertical_space(Length::Fixed(10f32)),
+            row![
+                horizontal_space(Length::Fixed(8f32)),
+                text(format!(
+                    "{} {} = {} {}",
+                    self.value, self.from, self.result, self.to
+                )),
+                horizontal_space(Length::Fixed(8f32)),
+            ],
+            vertical_space(Length::Fixed(10f32)),
+        ]
+        .into()
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        Ok(Some(LanchMessage::CopyToClipboard(self.result.clone())))
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for UnitSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unit conversion")
+    }
+}
+
+pub struct UnitsModule;
+
+impl SuggestionModule for UnitsModule {
+    fn name(&self) -> &str {
+        "units"
+    }
+
+    fn description(&self) -> &str {
+        "Converts between units, like \"12 in to cm\" or \"70 F in C\""
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (value, from, to) = match parse(query) {
+            Some(conversion) => conversion,
+            None => return Ok(()),
+        };
+        let result = match convert(value, from, to) {
+            Some(result) => result,
+            None => return Ok(()),
+        };
+
+        v.push((
+            MatchLevel::Exact,
+            Arc::new(UnitSuggestion {
+                value: format_value(value),
+                from: from.names[0],
+                result: format_value(result),
+                to: to.names[0],
+            }),
+        ));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversion(query: &str) -> Option<String> {
+        let (value, from, to) = parse(query)?;
+        convert(value, from, to).map(format_value)
+    }
+
+    #[test]
+    fn converts_between_units() {
+        assert_eq!(conversion("12 in to cm").as_deref(), Some("30.48"));
+        assert_eq!(conversion("1 mi in km").as_deref(), Some("1.609344"));
+        assert_eq!(conversion("3.5 GiB to MB").as_deref(), Some("3758.096384"));
+        assert_eq!(conversion("60 mph in km/h").as_deref(), Some("96.56064"));
+        assert_eq!(conversion("2 hours to min").as_deref(), Some("120"));
+    }
+
+    #[test]
+    fn temperatures_use_offsets() {
+        assert_eq!(conversion("70 F in C").as_deref(), Some("21.1111111111"));
+        assert_eq!(conversion("-40 C to F").as_deref(), Some("-40"));
+        assert_eq!(conversion("0 C to K").as_deref(), Some("273.15"));
+    }
+
+    #[test]
+    fn unit_names() {
+        // case only matters when it tells units apart
+        assert_eq!(
+            find_unit("mb").map(|u| u.names[0]),
+            find_unit("MB").map(|u| u.names[0])
+        );
+        assert_ne!(
+            find_unit("Mb").map(|u| u.names[0]),
+            find_unit("MB").map(|u| u.names[0])
+        );
+        assert_eq!(
+            find_unit("inches").map(|u| u.names[0]),
+            find_unit("in").map(|u| u.names[0])
+        );
+        assert!(find_unit("parsec").is_none());
+    }
+
+    #[test]
+    fn rejects_mismatched_or_incomplete_queries() {
+        assert_eq!(conversion("12 kg to cm"), None);
+        assert_eq!(conversion("12 cm"), None);
+        assert_eq!(conversion("cm to in"), None);
+    }
+
+    #[test]
+    fn numbers_come_first() {
+        assert_eq!(split_number("12in"), Some((Decimal::from(12), "in")));
+        assert_eq!(split_number("-40 C"), Some((Decimal::from(-40), " C")));
+        assert_eq!(split_number("in 12"), None);
+    }
+}
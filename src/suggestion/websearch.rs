@@ -0,0 +1,204 @@
+use iced::widget::{horizontal_space, row, text};
+use iced::{Element, Length};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use serde::Deserialize;
+
+use std::fmt::Display;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::launcher;
+
+use super::script::resolve_icon;
+use super::*;
+
+// used for engines that don't set an icon of their own
+const DEFAULT_ICON: &str = "web-browser";
+
+/// An entry of `[[modules.websearch.engines]]`
+#[derive(Debug, Deserialize)]
+struct EngineConfig {
+    keyword: String,
+    name: String,
+
+    // `{query}` is replaced by the percent encoded search terms
+    url: String,
+
+    #[serde(default)]
+    icon: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct WebSearchConfig {
+    // replaces the builtin engines if given
+    engines: Option<Vec<EngineConfig>>,
+
+    // keyword of the engine suggested when nothing else matches, empty to disable
+    fallback: Option<String>,
+}
+
+#[derive(Debug)]
+struct Engine {
+    keyword: String,
+    name: String,
+    url: String,
+    icon: Option<PathBuf>,
+}
+
+impl Engine {
+    fn search_url(&self, terms: &str) -> String {
+        self.url.replace(
+            "{query}",
+            &utf8_percent_encode(terms, NON_ALPHANUMERIC).to_string(),
+        )
+    }
+}
+
+fn builtin_engines() -> Vec<EngineConfig> {
+    [
+        ("g", "Google", "https://www.google.com/search?q={query}"),
+        ("ddg", "DuckDuckGo", "https://duckduckgo.com/?q={query}"),
+        (
+            "wiki",
+            "Wikipedia",
+            "https://en.wikipedia.org/w/index.php?search={query}",
+        ),
+    ]
+    .into_iter()
+    .map(|(keyword, name, url)| EngineConfig {
+        keyword: keyword.to_string(),
+        name: name.to_string(),
+        url: url.to_string(),
+        icon: None,
+    })
+    .collect()
+}
+
+#[derive(Debug)]
+pub struct SearchSuggestion {
+    engine: Arc<Engine>,
+    terms: String,
+}
+
+impl Suggestion for SearchSuggestion {
+    fn view(&self) -> Element<'_, LanchMessage> {
+        let label = text(format!(
+            "Search {} for \"{}\"",
+            self.engine.name, self.terms
+        ));
+
+        match self.engine.icon.as_deref().and_then(view_icon) {
+            Some(img) => row![img, horizontal_space(Length::Fixed(5f32)), label].into(),
+            None => label.into(),
+        }
+    }
+
+    fn execute(&self) -> Result<Option<LanchMessage>, Box<dyn std::error::Error>> {
+        launcher::open(&self.engine.search_url(&self.terms))?;
+        Ok(None)
+    }
+
+    fn matches(&self, _query: &str) -> MatchLevel {
+        MatchLevel::Exact
+    }
+}
+
+impl Display for SearchSuggestion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Web search")
+    }
+}
+
+/// Searches the web in the default browser, "g rust iced" searches Google for "rust iced"
+#[derive(Default)]
+pub struct WebSearchModule {
+    engines: Vec<Arc<Engine>>,
+    fallback: Option<Arc<Engine>>,
+}
+
+impl SuggestionModule for WebSearchModule {
+    fn name(&self) -> &str {
+        "websearch"
+    }
+
+    fn description(&self) -> &str {
+        "Web searches by engine keyword, like \"g rust iced\""
+    }
+
+    fn init(&mut self, ctx: &ModuleContext) -> Result<(), Box<dyn std::error::Error>> {
+        let config: WebSearchConfig = toml::Value::Table(ctx.settings.clone()).try_into()?;
+
+        self.engines = config
+            .engines
+            .unwrap_or_else(builtin_engines)
+            .into_iter()
+            .map(|engine| {
+                if !engine.url.contains("{query}") {
+                    return Err(format!("the url of {} has no {{query}}", engine.name));
+                }
+                let icon = engine.icon.as_deref().unwrap_or(DEFAULT_ICON);
+
+                Ok(Arc::new(Engine {
+                    icon: resolve_icon(icon, Some(ctx.icon_theme)),
+                    keyword: engine.keyword,
+                    name: engine.name,
+                    url: engine.url,
+                }))
+            })
+            .collect::<Result<_, _>>()?;
+
+        self.fallback = match config.fallback.as_deref() {
+            Some("") => None,
+            Some(keyword) => Some(
+                self.engines
+                    .iter()
+                    .find(|e| e.keyword == keyword)
+                    .cloned()
+                    .ok_or_else(|| format!("no engine with the keyword `{}`", keyword))?,
+            ),
+            None => self.engines.first().cloned(),
+        };
+        Ok(())
+    }
+
+    fn get_matches(
+        &self,
+        query: &str,
+        _cancel: &CancelToken,
+        v: &mut Vec<Match>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let (keyword, terms) = query.split_once(char::is_whitespace).unwrap_or((query, ""));
+        let terms = terms.trim();
+
+        let engine = self
+            .engines
+            .iter()
+            .find(|e| e.keyword.eq_ignore_ascii_case(keyword));
+
+        match engine {
+            Some(engine) if !terms.is_empty() => v.push((
+                MatchLevel::Exact,
+                Arc::new(SearchSuggestion {
+                    engine: Arc::clone(engine),
+                    terms: terms.to_string(),
+                }),
+            )),
+            // "g" on its own is more likely the start of something else
+            Some(_) => {}
+            None => {
+                if let Some(engine) = &self.fallback {
+                    v.push((
+                        MatchLevel::Fallback,
+                        Arc::new(SearchSuggestion {
+                            engine: Arc::clone(engine),
+                            terms: query.to_string(),
+                        }),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
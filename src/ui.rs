@@ -1,3 +1,5 @@
+use iced::futures::channel::mpsc;
+use iced::futures::StreamExt;
 use iced::widget::{
     column, container, horizontal_rule, horizontal_space, row, scrollable, text, text_input,
     vertical_space,
@@ -7,17 +9,24 @@ use iced::{
     Command, Element, Event, Length, Theme,
 };
 
-use std::collections::VecDeque;
-use std::rc::Rc;
-
 mod infobar;
+mod modules;
 mod settings;
 
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache::SharedCache;
+use crate::clipboard::{self, Copied};
+use crate::config::LanchConfig;
+use crate::icons::IconTheme;
+use crate::suggestion::registry::ModuleRegistry;
 use crate::suggestion::*;
 use settings::*;
 
-pub fn init() -> iced::Result {
-    Lanch::run(settings::settings())
+pub fn init(config: LanchConfig) -> iced::Result {
+    Lanch::run(settings::settings(config))
 }
 
 lazy_static::lazy_static! {
@@ -36,17 +45,29 @@ pub struct Lanch {
     // The current display layout of the application
     layout: Layout,
 
-    // this is a little bit of a workaround for now
-    executable_module: executable::ExecutableModule,
-
     // loaded modules providing extra suggestion functionality
-    modules: Vec<Box<dyn SuggestionModule>>,
+    modules: ModuleRegistry,
+
+    // name of the module the current query was routed to by its trigger
+    active_module: Option<String>,
 
     // the current query in the text box
     query: String,
 
-    // suggestions displayed to the user
-    suggestions: VecDeque<Rc<dyn Suggestion>>,
+    // incremented on every query change, results tagged with an older id are dropped
+    query_id: u64,
+
+    // cancels the modules still working on an outdated query
+    cancel: CancelToken,
+
+    // number of modules that haven't reported their matches for the current query yet
+    pending: usize,
+
+    // suggestions displayed to the user, best matches first
+    suggestions: Vec<Match>,
+
+    // held back until every module is done, shown if none of them found anything
+    fallbacks: Vec<Match>,
 
     // the currently selected suggestion
     selected: usize,
@@ -72,6 +93,8 @@ pub enum Direction {
 pub enum Layout {
     Default,
     License,
+    Modules,
+    #[allow(dead_code)] // TODO: help layout
     Help,
 }
 
@@ -83,6 +106,17 @@ pub enum LanchMessage {
     Escape,
     SwitchLayout(Layout),
     RefreshCache,
+    MatchesReady(u64, Vec<Match>, Option<String>),
+    ToggleModule(usize, bool),
+    // replaces the query and puts the cursor behind it
+    SetQuery(String),
+    CopyToClipboard(String),
+    // shows a message in the info bar instead of closing after an execute
+    Notify(String),
+    // redraws the suggestions that change by the second
+    Tick,
+    // the window manager wants the window gone
+    CloseRequested,
 }
 
 impl Application for Lanch {
@@ -92,22 +126,72 @@ impl Application for Lanch {
     type Theme = Theme;
 
     fn new(flags: Self::Flags) -> (Lanch, Command<Self::Message>) {
+        let icon_theme = IconTheme::detect(&flags.config.icons);
+        let mut modules = ModuleRegistry::new(icon_theme, flags.config.modules);
+
+        // loaded by the programs module, read by the ones opening files with those programs
+        let cache = SharedCache::default();
+
+        let mut loaded: Vec<Box<dyn SuggestionModule>> = vec![
+            Box::new(executable::ExecutableModule::new(Arc::clone(&cache))),
+            Box::new(open::OpenModule::new(cache)),
+            Box::<command::CommandModule>::default(),
+            Box::<custom::CustomModule>::default(),
+            Box::<websearch::WebSearchModule>::default(),
+            Box::<timedate::TimeDateModule>::default(),
+            Box::new(calculator::CalculatorModule),
+            Box::new(units::UnitsModule),
+            Box::<currency::CurrencyModule>::default(),
+            Box::new(builtin::BuiltInModule::new()),
+        ];
+        loaded.extend(
+            script::ScriptModule::discover()
+                .into_iter()
+                .map(|m| Box::new(m) as Box<dyn SuggestionModule>),
+        );
+
+        let mut errors = Vec::new();
+        for plugin in plugin::PluginModule::discover() {
+            match plugin {
+                Ok(plugin) => loaded.push(Box::new(plugin)),
+                Err(e) => errors.push(e),
+            }
+        }
+        for script in scripting::RhaiModule::discover() {
+            match script {
+                Ok(script) => loaded.push(Box::new(script)),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        errors.extend(
+            loaded
+                .into_iter()
+                .filter_map(|module| modules.register(module).err())
+                .map(|e| e.to_string()),
+        );
+
+        let mut info_bar = infobar::InfoBar::new();
+        if !errors.is_empty() {
+            info_bar.set_msg(Some(format!(" Error: {}", errors.join("; "))));
+        }
+
         (
             Lanch {
                 options: flags.options,
-                executable_module: executable::ExecutableModule::new(None),
-                modules: vec![
-                    Box::new(command::CommandModule),
-                    Box::new(timedate::TimeDateModule::new()),
-                    Box::new(builtin::BuiltInModule::new()),
-                ], 
+                modules,
+                active_module: None,
                 layout: Layout::Default,
                 query: String::new(),
-                suggestions: VecDeque::new(),
+                query_id: 0,
+                cancel: CancelToken::default(),
+                pending: 0,
+                suggestions: Vec::new(),
+                fallbacks: Vec::new(),
                 selected: 0,
                 page: 0,
                 theme: Theme::Dark,
-                info_bar: infobar::InfoBar::new(),
+                info_bar,
             },
             Command::batch(vec![
                 window::gain_focus(),
@@ -121,33 +205,63 @@ impl Application for Lanch {
     }
 
     fn update(&mut self, msg: Self::Message) -> Command<Self::Message> {
+        // results coming in and the clock ticking aren't the user doing something, a message
+        // set for the previous action stays up through them
+        if !matches!(msg, LanchMessage::MatchesReady(..) | LanchMessage::Tick) {
+            self.info_bar.clear();
+        }
+
         match msg {
             LanchMessage::QueryChanged(q) => {
                 self.query = q.trim_start().to_string();
                 self.selected = 0;
                 self.page = 0;
-                self.generate_suggestions();
 
-                if self.suggestions.is_empty() {
-                    return window::resize(
-                        self.options.window_size.0,
-                        self.options.font_size as u32 * 5,
-                    );
-                } else {
-                    // TODO: as of right now I haven't figured out a way to get the actual height
-                    // of what is rendered so we kinda "guess" with font size and the number of
-                    // elements. Works ok-ish for now, though problems start when the suggestions
-                    // are not the same height (like date/time).
-                    return window::resize(
-                        self.options.window_size.0,
-                        self.options.window_size.1.min(
-                            (self.suggestions.len().min(SUGGESTIONS_PER_PAGE) + 5)
-                                .saturating_mul(self.options.font_size as usize + 2)
-                                as u32,
-                        ),
-                    );
+                return Command::batch(vec![self.generate_suggestions(), self.resize_to_fit()]);
+            }
+            LanchMessage::MatchesReady(query_id, matches, error) => {
+                // results of an outdated query
+                if query_id != self.query_id {
+                    return Command::none();
+                }
+
+                if let Some(e) = error {
+                    self.info_bar.set_msg(Some(format!(" Error: {}", e)));
                 }
+
+                // the highlighted suggestion stays highlighted, enter shouldn't start something
+                // else because a slower module reported in right before it
+                let selected = self
+                    .suggestions
+                    .get(self.selected + self.page * SUGGESTIONS_PER_PAGE)
+                    .map(|(_, sg)| Arc::clone(sg));
+
+                self.pending = self.pending.saturating_sub(1);
+                let (fallbacks, matches): (Vec<_>, Vec<_>) = matches
+                    .into_iter()
+                    .partition(|(level, _)| *level == MatchLevel::Fallback);
+                self.suggestions.extend(matches);
+                self.fallbacks.extend(fallbacks);
+                if self.pending == 0 && self.suggestions.is_empty() {
+                    self.suggestions = std::mem::take(&mut self.fallbacks);
+                }
+                // stable, so the order the modules reported in is kept within a match level
+                self.suggestions.sort_by_key(|(level, _)| *level);
+
+                let moved = selected.and_then(|selected| {
+                    self.suggestions
+                        .iter()
+                        .position(|(_, sg)| Arc::ptr_eq(sg, &selected))
+                });
+                if let Some(i) = moved {
+                    self.page = i / SUGGESTIONS_PER_PAGE;
+                    self.selected = i % SUGGESTIONS_PER_PAGE;
+                }
+
+                return self.resize_to_fit();
             }
+            // nothing to move through while the modules are still searching
+            LanchMessage::NavigateList(_) if self.suggestions.is_empty() => {}
             LanchMessage::NavigateList(d) => match d {
                 Direction::Up => {
                     if self.selected == 0 && self.page != 0 {
@@ -175,13 +289,13 @@ impl Application for Lanch {
                 }
             },
             LanchMessage::ExecuteSelected => {
-                if let Some(sel) = self
+                if let Some((_, sel)) = self
                     .suggestions
                     .get(self.selected + self.page * SUGGESTIONS_PER_PAGE)
                 {
                     match sel.execute() {
                         Ok(Some(msg)) => return self.update(msg),
-                        Ok(None) => return window::close(),
+                        Ok(None) => return self.close(),
                         Err(e) => {
                             self.info_bar.set_msg(Some(format!(" Error: {}", e)));
                         }
@@ -189,14 +303,15 @@ impl Application for Lanch {
                 }
             }
             LanchMessage::Escape => match self.layout {
-                Layout::Help | Layout::License => {
+                Layout::Help | Layout::License | Layout::Modules => {
                     return Command::batch(vec![
                         self.update(LanchMessage::SwitchLayout(Layout::Default)),
                         text_input::focus(QUERY_INPUT_ID.clone()),
                     ])
                 }
-                Layout::Default => return window::close(),
+                Layout::Default => return self.close(),
             },
+            LanchMessage::CloseRequested => return self.close(),
             LanchMessage::SwitchLayout(layout) => {
                 self.layout = layout;
 
@@ -208,20 +323,70 @@ impl Application for Lanch {
                         )
                     }
                     Layout::License => return window::resize(700, 450),
+                    Layout::Modules => {
+                        return window::resize(
+                            self.options.window_size.0,
+                            self.options.window_size.1,
+                        )
+                    }
                     Layout::Help => unreachable!(),
                 }
             }
             LanchMessage::RefreshCache => {
-                self.executable_module.refresh_cache(None);
+                let errors = self.modules.refresh();
+
+                if errors.is_empty() {
+                    self.info_bar.set_color(Some(Color::from([0.04, 0.55, 0.35])));
+                    self.info_bar.set_msg(Some(String::from("cache: done")));
+                } else {
+                    self.info_bar
+                        .set_msg(Some(format!(" Error: {}", errors.join("; "))));
+                }
+            }
+            LanchMessage::ToggleModule(idx, enabled) => {
+                self.modules.set_enabled(idx, enabled);
+            }
+            LanchMessage::SetQuery(query) => {
+                return Command::batch(vec![
+                    self.update(LanchMessage::QueryChanged(query)),
+                    text_input::move_cursor_to_end(QUERY_INPUT_ID.clone()),
+                ]);
+            }
+            LanchMessage::Notify(msg) => {
+                self.info_bar.set_msg(Some(msg));
+            }
+            // nothing to update, handling a message is enough for iced to redraw
+            LanchMessage::Tick => {}
+            LanchMessage::CopyToClipboard(text) => {
+                // short values are repeated so it's clear what ended up on the clipboard
+                let mut copied = match text.chars().count() {
+                    1..=40 if !text.contains('\n') => format!("copied \"{}\" to clipboard", text),
+                    _ => String::from("copied to clipboard"),
+                };
+
+                let command = match clipboard::copy(&text) {
+                    Ok(Copied::Helper) => Command::none(),
+                    // only a clipboard manager keeps it around once lanch exits
+                    Ok(Copied::Window) => {
+                        copied.push_str(", install wl-copy or xclip to keep it after closing");
+                        iced::clipboard::write(text)
+                    }
+                    Err(e) => {
+                        self.info_bar.set_msg(Some(format!(" Error: {}", e)));
+                        return Command::none();
+                    }
+                };
                 self.info_bar.set_color(Some(Color::from([0.04, 0.55, 0.35])));
-                self.info_bar.set_msg(Some(String::from("cache: done")));
+                self.info_bar.set_msg(Some(copied));
+
+                return command;
             }
         }
 
         Command::none()
     }
 
-    fn view(&self) -> Element<Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
         match self.layout {
             // The default one column suggestion list layout
             Layout::Default => {
@@ -252,6 +417,8 @@ impl Application for Lanch {
                 .width(Length::Fill),
             )
             .into(),
+            // List of the loaded modules
+            Layout::Modules => modules::view(self),
             // Help menu
             Layout::Help => {
                 todo!()
@@ -260,13 +427,28 @@ impl Application for Lanch {
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
-        subscription::events_with(|event, _status| match event {
+        let mut subscriptions = vec![subscription::events_with(|event, _status| match event {
             Event::Keyboard(keyboard::Event::KeyPressed {
                 key_code,
                 modifiers,
             }) => Self::handle_key(key_code, modifiers),
+            Event::Window(window::Event::CloseRequested) => Some(LanchMessage::CloseRequested),
             _ => None,
-        })
+        })];
+
+        // only ticks while a clock is on screen, an idle launcher shouldn't keep redrawing
+        let live = self.layout == Layout::Default
+            && self
+                .suggestions
+                .iter()
+                .skip(SUGGESTIONS_PER_PAGE * self.page)
+                .take(SUGGESTIONS_PER_PAGE)
+                .any(|(_, sg)| sg.live());
+        if live {
+            subscriptions.push(every(Duration::from_secs(1)).map(|_| LanchMessage::Tick));
+        }
+
+        iced::Subscription::batch(subscriptions)
     }
 
     fn theme(&self) -> Self::Theme {
@@ -274,28 +456,105 @@ impl Application for Lanch {
     }
 }
 
+/// Produces a message every `interval`. `iced::time::every` needs an async runtime lanch doesn't
+/// use, so a thread does the waiting.
+fn every(interval: Duration) -> iced::Subscription<Instant> {
+    subscription::unfold(
+        ("every", interval),
+        None,
+        move |ticks: Option<mpsc::Receiver<Instant>>| async move {
+            let mut ticks = match ticks {
+                Some(ticks) => ticks,
+                None => {
+                    let (mut tx, rx) = mpsc::channel(1);
+                    thread::spawn(move || loop {
+                        thread::sleep(interval);
+                        // gone once the subscription ends, a full channel just skips a tick
+                        if let Err(e) = tx.try_send(Instant::now()) {
+                            if e.is_disconnected() {
+                                break;
+                            }
+                        }
+                    });
+                    rx
+                }
+            };
+            (ticks.next().await, Some(ticks))
+        },
+    )
+}
+
 impl Lanch {
-    // Updates the suggestions field based on the query
-    fn generate_suggestions(&mut self) {
+    // Starts querying the modules for the current query, their matches come back as
+    // `MatchesReady` messages
+    fn generate_suggestions(&mut self) -> Command<LanchMessage> {
+        self.cancel.cancel();
+        self.cancel = CancelToken::default();
+        self.query_id += 1;
         self.suggestions.clear();
+        self.fallbacks.clear();
+        self.pending = 0;
+        self.active_module = None;
 
         if self.query.is_empty() {
-            return;
+            return Command::none();
         }
 
-        let trimmed_query = self.query.trim();
+        let route = self.modules.route(self.query.trim());
+        self.active_module = route.active.map(|m| m.module.name().to_string());
+
+        let query_id = self.query_id;
+        let commands: Vec<_> = route
+            .targets
+            .into_iter()
+            .map(|(m, query)| {
+                Command::perform(
+                    m.worker.query(query.to_string(), self.cancel.clone()),
+                    move |(matches, error)| LanchMessage::MatchesReady(query_id, matches, error),
+                )
+            })
+            .collect();
+        self.pending = commands.len();
+
+        Command::batch(commands)
+    }
+
+    // Gives the modules a chance to clean up before the window closes
+    fn close(&self) -> Command<LanchMessage> {
+        self.modules.shutdown();
+        window::close()
+    }
 
-        self.executable_module.get_matches(trimmed_query, &mut self.suggestions);
-        for module in &mut self.modules {
-            module.get_matches(trimmed_query, &mut self.suggestions);
+    // Resizes the window to fit the current suggestions
+    fn resize_to_fit(&self) -> Command<LanchMessage> {
+        if self.suggestions.is_empty() {
+            window::resize(
+                self.options.window_size.0,
+                self.options.font_size as u32 * 5,
+            )
+        } else {
+            // TODO: as of right now I haven't figured out a way to get the actual height
+            // of what is rendered so we kinda "guess" with font size and the number of
+            // elements. Works ok-ish for now, though problems start when the suggestions
+            // are not the same height (like date/time).
+            window::resize(
+                self.options.window_size.0,
+                self.options.window_size.1.min(
+                    (self.suggestions.len().min(SUGGESTIONS_PER_PAGE) + 5)
+                        .saturating_mul(self.options.font_size as usize + 2)
+                        as u32,
+                ),
+            )
         }
     }
 
     // Turns the suggestion field into widgets
-    fn view_suggestions(&self) -> Element<LanchMessage> {
+    fn view_suggestions(&self) -> Element<'_, LanchMessage> {
         if self.suggestions.is_empty() {
             let info = if self.query.is_empty() {
                 "Enter a query to get suggestions."
+            } else if self.pending > 0 {
+                "Searching..."
             } else {
                 "No matches."
             };
@@ -316,7 +575,7 @@ impl Lanch {
                 .skip(SUGGESTIONS_PER_PAGE * self.page)
                 .take(SUGGESTIONS_PER_PAGE)
                 .enumerate()
-                .map(|(i, sg)| {
+                .map(|(i, (_, sg))| {
                     let elem = if i == self.selected {
                         container(sg.view())
                             .style(theme::Container::Custom(Box::new(
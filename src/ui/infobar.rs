@@ -1,5 +1,3 @@
-use std::cell::Cell;
-
 use super::{ContainerBackgroundStyle, Lanch, LanchMessage, SUGGESTIONS_PER_PAGE};
 use iced::widget::{container, row, text};
 use iced::{theme, Color, Element, Length};
@@ -10,31 +8,36 @@ lazy_static::lazy_static! {
 }
 
 pub struct InfoBar {
-    msg: Cell<Option<String>>,
-    color: Cell<Option<Color>>,
+    msg: Option<String>,
+    color: Option<Color>,
 }
 
 impl InfoBar {
     pub fn new() -> Self {
         Self {
-            msg: Cell::new(None),
-            color: Cell::new(None),
+            msg: None,
+            color: None,
         }
     }
 
-    pub fn set_msg(&self, msg: Option<String>) {
-        self.msg.replace(msg);
+    pub fn set_msg(&mut self, msg: Option<String>) {
+        self.msg = msg;
+    }
+
+    pub fn set_color(&mut self, color: Option<Color>) {
+        self.color = color;
     }
 
-    pub fn set_color(&self, color: Option<Color>) {
-        self.color.replace(color);
+    /// Goes back to showing the page info. Messages stay up until the next thing the user does,
+    /// redraws alone (like the clock ticking) don't clear them.
+    pub fn clear(&mut self) {
+        self.msg = None;
+        self.color = None;
     }
 
-    pub fn view(&self, upper: &Lanch) -> Element<LanchMessage> {
-        // if there is a message available this clears it automatically so it only gets displayed
-        // once (same with the color)
-        if let Some(msg) = &self.msg.take() {
-            let color = self.color.take().unwrap_or(*COLOR_WARN);
+    pub fn view(&self, upper: &Lanch) -> Element<'_, LanchMessage> {
+        if let Some(msg) = &self.msg {
+            let color = self.color.unwrap_or(*COLOR_WARN);
 
             container(text(msg))
                 .width(Length::Fill)
@@ -42,8 +45,14 @@ impl InfoBar {
                     ContainerBackgroundStyle::new(color),
                 )))
         } else {
+            let module = match &upper.active_module {
+                Some(name) => format!(" [{name}]"),
+                None => String::new(),
+            };
+
             container(row![text(format!(
-                " Page: {} [{}-{}/{}]",
+                "{} Page: {} [{}-{}/{}]",
+                module,
                 upper.page,
                 SUGGESTIONS_PER_PAGE * upper.page,
                 SUGGESTIONS_PER_PAGE * upper.page
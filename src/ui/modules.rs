@@ -0,0 +1,28 @@
+use super::{Lanch, LanchMessage};
+use iced::widget::{checkbox, column, horizontal_space, row, scrollable, text};
+use iced::{theme, Color, Element, Length};
+
+// Lists the loaded modules and lets the user toggle them for the current session
+pub fn view(upper: &Lanch) -> Element<'_, LanchMessage> {
+    let rows = upper.modules.iter().enumerate().map(|(i, m)| {
+        let trigger = match m.trigger() {
+            Some(trigger) => format!("\"{trigger}\""),
+            None => String::from("-"),
+        };
+
+        row![
+            horizontal_space(Length::Fixed(10f32)),
+            checkbox(m.module.name(), m.enabled(), move |enabled| {
+                LanchMessage::ToggleModule(i, enabled)
+            }),
+            horizontal_space(Length::Fill),
+            text(m.module.description()).style(theme::Text::Color(Color::from([0.5, 0.5, 0.5]))),
+            horizontal_space(Length::Fixed(10f32)),
+            text(trigger),
+            horizontal_space(Length::Fixed(20f32)),
+        ]
+        .into()
+    });
+
+    scrollable(column(rows.collect()).spacing(5).width(Length::Fill)).into()
+}
@@ -1,8 +1,11 @@
 use iced::{window, Settings};
 
+use crate::config::LanchConfig;
+
 #[derive(Default, Debug)]
 pub struct LanchFlags {
     pub options: LanchOptions,
+    pub config: LanchConfig,
 }
 
 #[derive(Default, Debug)]
@@ -11,17 +14,21 @@ pub struct LanchOptions {
     pub font_size: f32,
 }
 
-pub fn settings() -> iced::Settings<LanchFlags> {
+pub fn settings(config: LanchConfig) -> iced::Settings<LanchFlags> {
     let mut settings: iced::Settings<LanchFlags> = Settings {
         flags: LanchFlags {
             options: LanchOptions {
                 window_size: (600, 400),
                 font_size: 20f32,
             },
+            config,
         },
         ..Default::default()
     };
 
+    // closing goes through `Lanch::close` so the modules get to save their state
+    settings.exit_on_close_request = false;
+
     settings.id = Some(String::from("lanch")); // this sets the WM_CLASS on x11 and makes it easy
                                                // to define the window as floating in tiling
                                                // window managers